@@ -0,0 +1,314 @@
+use crate::{core::Res, utils::log::Level};
+use anyhow::{anyhow, bail, Context};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const USAGE: &str = "\
+usage: neonano [options] [+line[,column]] [file[:line[:column]] | dir | -]...
+
+options:
+  -R, --readonly           open files without allowing edits
+      --pager              page through files like less, implies --readonly
+  -f, --follow             follow files as they grow, like tail -f
+      --log-file <path>    write the log to <path> instead of the state dir
+      --log-level <level>  one of error, warn, info, debug or trace
+  -h, --help               print this message and exit
+  -V, --version            print the version and exit
+      --                   treat all following arguments as paths
+
+a +line target applies to the file after it, or to the file before it when
+it comes last. directories are opened in the file picker. a single - reads
+the buffer from stdin, and when stdout is not a terminal the edited buffer
+is written to it on exit.
+";
+
+#[derive(Debug)]
+pub enum Action {
+    Edit(Args),
+    Help,
+    Version,
+}
+
+#[derive(Default, Debug)]
+pub struct Args {
+    pub targets: Vec<Target>,
+    pub readonly: bool,
+    pub pager: bool,
+    pub follow: bool,
+    pub log_file: Option<PathBuf>,
+    pub log_level: Option<Level>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Target {
+    File {
+        path: PathBuf,
+        position: Option<Position>,
+    },
+    Dir(PathBuf),
+    Stdin,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Res<Action> {
+    let mut parsed = Args::default();
+    let mut pending = None;
+    let mut only_paths = false;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if !only_paths && arg.starts_with('-') && arg != "-" {
+            let (option, inline) = match arg.split_once('=') {
+                Some((option, value)) => (option, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .with_context(|| format!("option '{option}' needs a value"))
+            };
+            let flag = || match inline {
+                Some(_) => Err(anyhow!("option '{option}' does not take a value")),
+                None => Ok(true),
+            };
+
+            match option {
+                "--" => only_paths = flag()?,
+                "-h" | "--help" => {
+                    flag()?;
+                    return Ok(Action::Help);
+                }
+                "-V" | "--version" => {
+                    flag()?;
+                    return Ok(Action::Version);
+                }
+                "-R" | "--readonly" => parsed.readonly = flag()?,
+                "--pager" => parsed.pager = flag()?,
+                "-f" | "--follow" => parsed.follow = flag()?,
+                "--log-file" => parsed.log_file = Some(value()?.into()),
+                "--log-level" => parsed.log_level = Some(value()?.parse()?),
+                _ => bail!("unknown option '{arg}'"),
+            }
+        } else if let (false, Some(position)) = (only_paths, arg.strip_prefix('+')) {
+            pending = Some(parse_position(position)?);
+        } else if !only_paths && arg == "-" {
+            if parsed.targets.contains(&Target::Stdin) {
+                bail!("stdin can only be read once");
+            }
+            if pending.is_some() {
+                bail!("line target given before stdin");
+            }
+            parsed.targets.push(Target::Stdin);
+        } else {
+            let target = parse_target(arg);
+
+            parsed.targets.push(match (target, pending.take()) {
+                (Target::File { path, position }, pending) => Target::File {
+                    path,
+                    position: pending.or(position),
+                },
+                (Target::Dir(dir), Some(_)) => {
+                    bail!("line target given before directory {}", dir.display())
+                }
+                (other, _) => other,
+            });
+        }
+    }
+
+    if let Some(pending) = pending {
+        match parsed.targets.last_mut() {
+            Some(Target::File { position, .. }) => *position = Some(pending),
+            _ => bail!("line target given without a file"),
+        }
+    }
+
+    Ok(Action::Edit(parsed))
+}
+
+fn parse_target(arg: String) -> Target {
+    let path = Path::new(&arg);
+
+    if path.is_dir() {
+        return Target::Dir(arg.into());
+    }
+
+    if !path.exists() {
+        if let Some((path, position)) = split_position(&arg) {
+            return Target::File {
+                path: path.into(),
+                position: Some(position),
+            };
+        }
+    }
+
+    Target::File {
+        path: arg.into(),
+        position: None,
+    }
+}
+
+fn split_position(arg: &str) -> Option<(&str, Position)> {
+    let (rest, last) = arg.rsplit_once(':')?;
+    let last = last.parse::<usize>().ok()?.max(1);
+    let line = rest
+        .rsplit_once(':')
+        .and_then(|(path, line)| Some((path, line.parse::<usize>().ok()?.max(1))));
+
+    let (path, position) = match line {
+        Some((path, line)) => (path, Position { line, column: last }),
+        None => (
+            rest,
+            Position {
+                line: last,
+                column: 1,
+            },
+        ),
+    };
+
+    (!path.is_empty()).then_some((path, position))
+}
+
+fn parse_position(position: &str) -> Res<Position> {
+    position
+        .parse()
+        .map_err(|_| anyhow!("invalid line target '+{position}'"))
+}
+
+impl FromStr for Position {
+    type Err = anyhow::Error;
+
+    fn from_str(position: &str) -> Res<Self> {
+        let (line, column) = position.split_once([',', ':']).unwrap_or((position, "1"));
+        let number = |text: &str| {
+            text.trim()
+                .parse::<usize>()
+                .map(|number| number.max(1))
+                .map_err(|_| anyhow!("invalid position '{position}'"))
+        };
+
+        Ok(Self {
+            line: number(line)?,
+            column: number(column)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(args: &[&str]) -> Args {
+        match parse(args.iter().map(|arg| arg.to_string())).unwrap() {
+            Action::Edit(args) => args,
+            action => panic!("expected edit, got {action:?}"),
+        }
+    }
+
+    fn file(path: &str, position: Option<(usize, usize)>) -> Target {
+        Target::File {
+            path: path.into(),
+            position: position.map(|(line, column)| Position { line, column }),
+        }
+    }
+
+    #[test]
+    fn no_args() {
+        assert!(edit(&[]).targets.is_empty());
+    }
+
+    #[test]
+    fn flags() {
+        assert!(matches!(parse(["--help".into()]).unwrap(), Action::Help));
+        assert!(matches!(parse(["-V".into()]).unwrap(), Action::Version));
+        assert!(edit(&["-R"]).readonly);
+        assert!(edit(&["--pager"]).pager);
+        assert!(edit(&["-f"]).follow);
+        assert!(parse(["--bogus".into()]).is_err());
+        assert!(parse(["-R=x".into()]).is_err());
+        assert!(parse(["--pager=x".into()]).is_err());
+        assert!(parse(["--help=x".into()]).is_err());
+    }
+
+    #[test]
+    fn log_options() {
+        let args = edit(&["--log-file", "neonano.log", "--log-level=debug"]);
+
+        assert_eq!(args.log_file, Some("neonano.log".into()));
+        assert_eq!(args.log_level, Some(Level::Debug));
+        assert!(parse(["--log-level".into()]).is_err());
+        assert!(parse(["--log-level=loud".into()]).is_err());
+    }
+
+    #[test]
+    fn line_targets() {
+        let args = edit(&["+3", "a.txt", "b.txt", "+42,7"]);
+
+        assert_eq!(
+            args.targets,
+            [file("a.txt", Some((3, 1))), file("b.txt", Some((42, 7)))]
+        );
+        assert!(parse(["+42".into()]).is_err());
+        assert!(parse(["+x".into(), "a.txt".into()]).is_err());
+        assert!(parse(["+3".into(), "-".into()]).is_err());
+        assert!(parse(["+3".into(), "src".into(), "a.txt".into()]).is_err());
+    }
+
+    #[test]
+    fn colon_targets() {
+        let args = edit(&["missing.txt:12:5", "other.txt:8", "plain.txt"]);
+
+        assert_eq!(
+            args.targets,
+            [
+                file("missing.txt", Some((12, 5))),
+                file("other.txt", Some((8, 1))),
+                file("plain.txt", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn positions() {
+        let position = |text: &str| text.parse::<Position>().ok();
+
+        assert_eq!(
+            position("12"),
+            Some(Position {
+                line: 12,
+                column: 1
+            })
+        );
+        assert_eq!(
+            position("12:5"),
+            Some(Position {
+                line: 12,
+                column: 5
+            })
+        );
+        assert_eq!(position(" 0, 3 "), Some(Position { line: 1, column: 3 }));
+        assert_eq!(position("twelve"), None);
+    }
+
+    #[test]
+    fn stdin() {
+        assert_eq!(edit(&["-"]).targets, [Target::Stdin]);
+        assert_eq!(edit(&["--", "-"]).targets, [file("-", None)]);
+        assert!(parse(["-".into(), "-".into()]).is_err());
+    }
+
+    #[test]
+    fn dirs_and_separator() {
+        let args = edit(&["src", "--", "-R"]);
+
+        assert_eq!(args.targets, [Target::Dir("src".into()), file("-R", None)]);
+        assert!(!args.readonly);
+    }
+}
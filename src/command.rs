@@ -0,0 +1,110 @@
+use anyhow::anyhow;
+use std::{fmt, str::FromStr};
+
+macro_rules! commands {
+    ($($command:ident => $name:literal,)*) => {
+        #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+        pub enum Command {
+            $($command,)*
+        }
+
+        impl Command {
+            pub const ALL: &'static [Self] = &[$(Self::$command,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Self::$command => $name,)*
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    Quit => "app.quit",
+    Messages => "app.messages",
+    Palette => "app.palette",
+    ReloadConfig => "app.reload-config",
+    TileNext => "tile.next",
+    TilePrev => "tile.prev",
+    TileLog => "tile.log",
+    TileSplit => "tile.split",
+    TileFocus => "tile.focus",
+    Close => "buffer.close",
+    Save => "buffer.save",
+    SaveAs => "buffer.save-as",
+    CycleEnding => "buffer.cycle-ending",
+    Follow => "buffer.follow",
+    ReadOnly => "buffer.read-only",
+    CursorUp => "cursor.up",
+    CursorDown => "cursor.down",
+    CursorLeft => "cursor.left",
+    CursorRight => "cursor.right",
+    WordLeft => "cursor.word-left",
+    WordRight => "cursor.word-right",
+    LineStart => "cursor.line-start",
+    LineEnd => "cursor.line-end",
+    Top => "cursor.top",
+    Bottom => "cursor.bottom",
+    Goto => "cursor.goto",
+    PageUp => "view.page-up",
+    PageDown => "view.page-down",
+    Newline => "edit.newline",
+    OpenAbove => "edit.open-above",
+    OpenBelow => "edit.open-below",
+    Tab => "edit.tab",
+    Backspace => "edit.backspace",
+    BackspaceWord => "edit.backspace-word",
+    Delete => "edit.delete",
+    DeleteWord => "edit.delete-word",
+    SetMark => "edit.set-mark",
+    ClearMark => "edit.clear-mark",
+    KillLine => "edit.kill-line",
+    KillRegion => "edit.kill-region",
+    CopyRegion => "edit.copy-region",
+    Yank => "edit.yank",
+    YankPop => "edit.yank-pop",
+    SearchStart => "search.start",
+    SearchNext => "search.next",
+    NormalMode => "mode.normal",
+    MacroRecord => "macro.record",
+    MacroStop => "macro.stop",
+    MacroPlay => "macro.play",
+    PickerOpen => "picker.open",
+    PickerBack => "picker.back",
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Command {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|command| command.name() == name)
+            .ok_or_else(|| anyhow!("unknown command '{name}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for &command in Command::ALL {
+            assert_eq!(command.name().parse::<Command>().unwrap(), command);
+        }
+    }
+
+    #[test]
+    fn unknown_name() {
+        assert!("buffer.explode".parse::<Command>().is_err());
+    }
+}
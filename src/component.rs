@@ -1,8 +1,14 @@
 pub mod frame;
 
 mod content;
+mod dialog;
 mod filepicker;
+mod hex;
+mod largefile;
 mod line;
-mod portal;
+mod logviewer;
+mod minibuffer;
+mod palette;
+pub mod portal;
 mod screen;
 mod window;
@@ -0,0 +1,133 @@
+use crate::{
+    core::Res,
+    message::{Input, Key, KeyCombo, Message},
+    pressed,
+    utils::{
+        diff::{self, Change},
+        out::{self, Bounds, Out},
+    },
+};
+use crossterm::{
+    cursor::{Hide, MoveDown, MoveTo, MoveToColumn},
+    queue,
+    style::{self, Color, Print, PrintStyledContent, Stylize},
+};
+
+const DIFF_CHOICE: char = 'd';
+
+#[derive(Clone, Debug)]
+pub struct Dialog {
+    message: String,
+    choices: Vec<(char, &'static str)>,
+    diff: Vec<(char, String)>,
+    showing_diff: bool,
+    scroll: usize,
+}
+
+impl Dialog {
+    pub fn new(message: impl Into<String>, choices: &[(char, &'static str)]) -> Self {
+        Self {
+            message: message.into(),
+            choices: choices.to_vec(),
+            diff: vec![],
+            showing_diff: false,
+            scroll: 0,
+        }
+    }
+
+    pub fn with_diff(mut self, old: &str, new: &str) -> Self {
+        self.diff = diff::lines(old, new)
+            .into_iter()
+            .map(|change| match change {
+                Change::Same(line) => (' ', line.into()),
+                Change::Removed(line) => ('-', line.into()),
+                Change::Added(line) => ('+', line.into()),
+            })
+            .collect();
+        self.choices.push((DIFF_CHOICE, "show diff"));
+
+        self
+    }
+
+    pub fn update(&mut self, message: &Message) -> Option<char> {
+        match message {
+            &pressed!(Key::Char(DIFF_CHOICE)) if !self.diff.is_empty() => {
+                self.showing_diff = !self.showing_diff;
+                self.scroll = 0;
+
+                None
+            }
+
+            pressed!(Key::Up) | Message::Input(Input::ScrollUp) if self.showing_diff => {
+                self.scroll = self.scroll.saturating_sub(1);
+
+                None
+            }
+
+            pressed!(Key::Down) | Message::Input(Input::ScrollDown) if self.showing_diff => {
+                self.scroll = (self.scroll + 1).min(self.diff.len().saturating_sub(1));
+
+                None
+            }
+
+            &Message::Input(Input::KeyCombo(KeyCombo {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+                ..
+            })) => self
+                .choices
+                .iter()
+                .any(|&(choice, _)| choice == c)
+                .then_some(c),
+
+            _ => None,
+        }
+    }
+
+    pub fn view(&self, out: &mut Out, bounds: Bounds) -> Res {
+        queue!(out, Hide)?;
+        out::clear(out, bounds)?;
+        out::anchor(out, bounds)?;
+
+        let width = usize::from(bounds.width());
+        let fit = |line: &str| line.chars().take(width).collect::<String>();
+        let choices = self
+            .choices
+            .iter()
+            .map(|(c, label)| format!("[{c}] {label}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        if self.showing_diff {
+            let rows = usize::from(bounds.height()).saturating_sub(1);
+
+            for (sign, line) in self.diff.iter().skip(self.scroll).take(rows) {
+                let color = match sign {
+                    '+' => Color::Green,
+                    '-' => Color::Red,
+                    _ => Color::Reset,
+                };
+
+                queue!(
+                    out,
+                    PrintStyledContent(style::style(fit(&format!("{sign} {line}"))).with(color)),
+                    MoveDown(1),
+                    MoveToColumn(bounds.x0),
+                )?;
+            }
+        } else {
+            for line in self.message.lines() {
+                queue!(out, Print(fit(line)), MoveDown(1), MoveToColumn(bounds.x0))?;
+            }
+        }
+
+        queue!(
+            out,
+            MoveTo(bounds.x0, bounds.y1.saturating_sub(1)),
+            PrintStyledContent(style::style(fit(&choices)).reverse()),
+        )?;
+
+        Ok(())
+    }
+}
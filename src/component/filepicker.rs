@@ -1,9 +1,12 @@
 use crate::{
-    component::frame::StatusLine,
+    command::Command,
+    component::frame::StatusFields,
     core::Res,
-    message::{Key, Message},
-    pressed,
-    utils::out::{self, Bounds, Out},
+    message::Message,
+    utils::{
+        out::{self, Bounds, Out},
+        shared,
+    },
 };
 use anyhow::Context;
 use crossterm::{
@@ -13,14 +16,10 @@ use crossterm::{
 };
 use std::{
     env,
-    fmt::Write,
     fs::{self, FileType},
     path::PathBuf,
 };
 
-const DIR_ICON: char = '📂';
-const FILE_ICON: char = '📄';
-
 #[derive(Clone, Debug)]
 pub struct FilePickerEntry {
     path: PathBuf,
@@ -37,10 +36,14 @@ pub struct FilePicker {
 
 impl FilePicker {
     pub fn new(bounds: Bounds) -> Res<Self> {
+        Self::in_dir(env::current_dir()?, bounds)
+    }
+
+    pub fn in_dir(dir: impl Into<PathBuf>, bounds: Bounds) -> Res<Self> {
         let mut filepicker = Self {
             entries: vec![],
             selected: 0,
-            history: vec![env::current_dir()?],
+            history: vec![dir.into()],
             bounds,
         };
         filepicker.open()?;
@@ -68,9 +71,19 @@ impl FilePicker {
         self.bounds
     }
 
+    pub fn set_bounds(&mut self, bounds: Bounds) {
+        self.bounds = bounds;
+    }
+
     pub fn update(&mut self, message: &Message) -> Res<Option<Message>> {
         match message {
-            pressed!(Key::Up) => {
+            Message::Command(Command::CursorUp | Command::CursorDown | Command::PickerOpen)
+                if self.entries.is_empty() =>
+            {
+                Ok(None)
+            }
+
+            Message::Command(Command::CursorUp) => {
                 self.selected = if self.selected == 0 {
                     self.entries.len() - 1
                 } else {
@@ -80,7 +93,7 @@ impl FilePicker {
                 Ok(None)
             }
 
-            pressed!(Key::Down) => {
+            Message::Command(Command::CursorDown) => {
                 self.selected = if self.selected == self.entries.len() - 1 {
                     0
                 } else {
@@ -90,8 +103,11 @@ impl FilePicker {
                 Ok(None)
             }
 
-            pressed!(Key::Enter) => {
-                let dir = &self.entries[self.selected];
+            Message::Command(Command::PickerOpen) => {
+                let dir = self
+                    .entries
+                    .get(self.selected)
+                    .context("selected is valid")?;
 
                 if dir.file_type.is_file() {
                     Ok(Some(Message::Open(dir.path.clone())))
@@ -105,7 +121,7 @@ impl FilePicker {
                 }
             }
 
-            pressed!(Key::Esc) => {
+            Message::Command(Command::PickerBack) => {
                 if let Some(prev) = self.history.pop() {
                     if self.history.is_empty() {
                         self.history.push(prev);
@@ -121,29 +137,40 @@ impl FilePicker {
         }
     }
 
-    pub fn status(&self, statuses: &mut StatusLine) -> Res {
-        match statuses {
-            StatusLine::Top(left, middle, right) => {
-                write!(left, "Filepicker Top Left")?;
-                write!(middle, "Filepicker Top")?;
-                write!(right, "Filepicker Top Right")?;
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
+        let dir = self.history.last().context("history never empty")?;
 
-                Ok(())
-            }
-            StatusLine::Bottom(left, middle, right) => {
-                write!(left, "Filepicker Bottom Left")?;
-                write!(middle, "Filepicker Bottom")?;
-                write!(right, "Filepicker Bottom Right")?;
-                Ok(())
-            }
+        fields.set("path", dir.display())?;
+        fields.set("lines", self.entries.len())?;
+        fields.set("size", format_args!("{} entries", self.entries.len()))?;
+
+        if let Some(entry) = self.entries.get(self.selected) {
+            let name = entry.path.file_name().unwrap_or(entry.path.as_os_str());
+
+            fields.set("name", name.to_string_lossy())?;
+            fields.set("line", self.selected + 1)?;
+            fields.set(
+                "position",
+                format_args!("{}/{}", self.selected + 1, self.entries.len()),
+            )?;
+            fields.set("format", name.to_string_lossy())?;
         }
+
+        Ok(())
     }
 
     pub fn view(&self, out: &mut Out, active: bool) -> Res {
         queue!(out, Hide)?;
         out::anchor(out, self.bounds)?;
 
-        for (i, dir) in self.entries.iter().enumerate() {
+        let (picker, colors) =
+            shared::get(|shared| (shared.config.picker.clone(), shared.config.colors.clone()));
+
+        let height = usize::from(self.bounds.height());
+        let start = (self.selected + 1).saturating_sub(height);
+        let shown = self.entries.len().saturating_sub(start).min(height);
+
+        for (i, dir) in self.entries.iter().enumerate().skip(start).take(shown) {
             let highlight = active && i == self.selected;
 
             queue!(
@@ -154,19 +181,19 @@ impl FilePicker {
                     style::style(format_args!(
                         "{} {}",
                         if dir.file_type.is_dir() {
-                            DIR_ICON
+                            picker.dir_icon
                         } else {
-                            FILE_ICON
+                            picker.file_icon
                         },
                         dir.path.display()
                     ))
                     .with(if highlight {
-                        Color::Black
+                        colors.highlight_text
                     } else {
-                        Color::White
+                        colors.text
                     })
                     .on(if highlight {
-                        Color::White
+                        colors.highlight
                     } else {
                         Color::Reset
                     })
@@ -176,11 +203,11 @@ impl FilePicker {
             )?;
         }
 
-        if self.entries.len() < self.bounds.height().into() {
+        if shown < height {
             out::clear(
                 out,
                 Bounds {
-                    y0: self.bounds.y0 + u16::try_from(self.entries.len())?,
+                    y0: self.bounds.y0 + u16::try_from(shown)?,
                     ..self.bounds
                 },
             )?;
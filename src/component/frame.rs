@@ -1,35 +1,532 @@
 use crate::{
-    component::window::Window,
-    core::Res,
-    message::{Key, Message},
+    args::Args,
+    command::Command,
+    component::{
+        dialog::Dialog,
+        line::{self, char_display_width},
+        logviewer,
+        minibuffer::Minibuffer,
+        palette::Palette,
+        portal::Portal,
+        screen::Content,
+        window::Window,
+    },
+    core::{self, Res},
+    keymap::{self, Context, Resolution},
+    message::{Completer, Input, Key, KeyCombo, Message, PromptKind},
     pressed,
-    utils::out::{self, Bounds, Out},
+    utils::{
+        log::{self, Level, Record},
+        macros::{self, Macros},
+        out::{self, Bounds, Out},
+        shared, template,
+    },
 };
-use crossterm::{cursor::MoveRight, queue, style::Print};
+use crossterm::{
+    cursor::MoveRight,
+    queue,
+    style::{Color, Print, SetBackgroundColor},
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Write},
+    iter,
+    time::{Duration, Instant, SystemTime},
+};
+
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(4);
+const KEPT_NOTIFICATIONS: usize = 200;
+const CHORD_TIMEOUT: Duration = Duration::from_millis(1500);
+const KEPT_PROMPTS: usize = 50;
+const MAX_MACRO_DEPTH: usize = 10;
+const MAX_REPLAYED_KEYS: usize = 100_000;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Leave {
+    Close,
+    Quit,
+}
 
 #[derive(Debug)]
 pub struct Frame {
     top: StatusBar,
     bottom: StatusBar,
     window: Window,
+    notifications: VecDeque<Record>,
+    expires: Option<Instant>,
+    history: Option<usize>,
+    history_bounds: Bounds,
+    chord: Vec<KeyCombo>,
+    chord_expires: Option<Instant>,
+    palette: Option<Palette>,
+    minibuffer: Option<Minibuffer>,
+    prompt_history: HashMap<PromptKind, Vec<String>>,
+    macros: Macros,
+    playing: usize,
+    replay_budget: Option<usize>,
+    leaving: Option<(Dialog, Leave)>,
 }
 
 impl Frame {
-    pub fn new(bounds: Bounds) -> Res<Self> {
+    pub fn new(bounds: Bounds, args: &Args) -> Res<Self> {
         let [top_bar_bounds, rest] = bounds.hsplit(1);
         let [window_bounds, bottom_bar_bounds] = rest.hsplit(bounds.y1 - 1);
-        let window = Window::new(window_bounds)?;
+        let window = Window::new(window_bounds, args)?;
 
-        Ok(Self {
-            top: StatusBar::new(top_bar_bounds, StatusLine::top(), &window)?,
-            bottom: StatusBar::new(bottom_bar_bounds, StatusLine::bottom(), &window)?,
+        let mut frame = Self {
+            top: StatusBar::new(top_bar_bounds, StatusLine::top()),
+            bottom: StatusBar::new(bottom_bar_bounds, StatusLine::bottom()),
             window,
-        })
+            notifications: VecDeque::new(),
+            expires: None,
+            history: None,
+            history_bounds: window_bounds,
+            chord: vec![],
+            chord_expires: None,
+            palette: None,
+            minibuffer: None,
+            prompt_history: HashMap::new(),
+            macros: Macros::default(),
+            playing: 0,
+            replay_budget: None,
+            leaving: None,
+        };
+        if shared::get(|shared| shared.config.macros.persist) {
+            match Macros::load() {
+                Ok(macros) => frame.macros = macros,
+                Err(error) => frame.notify(Level::Error, &format!("{error:#}")),
+            }
+        }
+        frame.refresh()?;
+
+        Ok(frame)
+    }
+
+    fn refresh(&mut self) -> Res {
+        let mut fields = StatusFields::default();
+        self.window.status(&mut fields)?;
+        if let Some(name) = self.macros.recording() {
+            fields.set("macro", format_args!("recording @{name}"))?;
+        }
+        self.top.line.render(&fields);
+        self.bottom.line.render(&fields);
+
+        Ok(())
+    }
+
+    fn notify(&mut self, level: Level, text: &str) {
+        log::log(level, format_args!("{text}"));
+
+        let record = Record {
+            time: SystemTime::now(),
+            level,
+            message: text.into(),
+        };
+
+        if self.notifications.len() == KEPT_NOTIFICATIONS {
+            self.notifications.pop_front();
+        }
+        self.notifications.push_back(record.clone());
+        self.bottom.notice = Some(record);
+        self.expires = Some(Instant::now() + NOTIFY_TIMEOUT);
+    }
+
+    fn scroll_history(&mut self, message: &Message) {
+        let Some(scroll) = self.history else {
+            return;
+        };
+        let height = usize::from(self.history_bounds.height());
+        let max = self.notifications.len().saturating_sub(height);
+        let scroll_dist = shared::get(|shared| shared.config.editor.scroll_dist);
+
+        self.history = match message {
+            pressed!(Key::Esc) => None,
+            pressed!(Key::Home) => Some(max),
+            pressed!(Key::End) => Some(0),
+            pressed!(Key::Up) => Some(scroll + 1),
+            pressed!(Key::Down) => Some(scroll.saturating_sub(1)),
+            pressed!(Key::PageUp) => Some(scroll + height / 2),
+            pressed!(Key::PageDown) => Some(scroll.saturating_sub(height / 2)),
+            Message::Input(Input::ScrollUp) => Some(scroll + scroll_dist),
+            Message::Input(Input::ScrollDown) => Some(scroll.saturating_sub(scroll_dist)),
+            _ => Some(scroll),
+        }
+        .map(|scroll| scroll.min(max));
+    }
+
+    fn context(&self) -> Context {
+        if self.history.is_some()
+            || self.palette.is_some()
+            || self.minibuffer.is_some()
+            || self.leaving.is_some()
+        {
+            Context::Prompt
+        } else {
+            self.window.context()
+        }
+    }
+
+    fn prompt(&mut self, kind: PromptKind, text: &str, complete: Option<Completer>) -> Res {
+        let history = self.prompt_history.get(&kind).cloned().unwrap_or_default();
+        self.minibuffer = Some(Minibuffer::new(
+            kind,
+            text,
+            complete,
+            history,
+            self.bottom.bounds,
+        )?);
+
+        Ok(())
+    }
+
+    fn remember(&mut self, kind: PromptKind, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        let history = self.prompt_history.entry(kind).or_default();
+        history.retain(|entry| entry != text);
+        if history.len() == KEPT_PROMPTS {
+            history.remove(0);
+        }
+        history.push(text.into());
+    }
+
+    fn targets(&mut self, leave: Leave) -> Vec<&mut Content> {
+        match leave {
+            Leave::Close => self.window.active_content_mut().into_iter().collect(),
+            Leave::Quit => self.window.contents_mut().collect(),
+        }
+    }
+
+    fn leave(&mut self, leave: Leave) -> Res<Option<Message>> {
+        let unsaved: Vec<_> = self
+            .targets(leave)
+            .into_iter()
+            .filter_map(|content| content.unsaved())
+            .collect();
+        if unsaved.is_empty() {
+            return self.proceed(leave);
+        }
+
+        let dialog = Dialog::new(
+            format!("unsaved changes in\n{}", unsaved.join("\n")),
+            &[('s', "save"), ('x', "discard"), ('c', "cancel")],
+        );
+        self.leaving = Some((dialog, leave));
+
+        Ok(None)
+    }
+
+    fn resolve_leave(&mut self, leave: Leave, choice: char) -> Res<Option<Message>> {
+        if choice == 'c' {
+            return Ok(None);
+        }
+
+        let mut notices = vec![];
+        for content in self.targets(leave) {
+            if content.unsaved().is_none() {
+                continue;
+            }
+            match choice {
+                's' => notices.extend(content.save()?),
+                _ => content.discard()?,
+            }
+        }
+        for notice in notices {
+            if let Message::Notify { level, text } = notice {
+                self.notify(level, &text);
+            }
+        }
+
+        let unsaved = self
+            .targets(leave)
+            .iter()
+            .any(|content| content.unsaved().is_some());
+        if unsaved {
+            self.refresh()?;
+            return Ok(None);
+        }
+
+        self.proceed(leave)
+    }
+
+    fn proceed(&mut self, leave: Leave) -> Res<Option<Message>> {
+        match leave {
+            Leave::Close => {
+                let update = self.window.update(&Message::Command(Command::Close))?;
+                self.refresh()?;
+
+                Ok(update)
+            }
+            Leave::Quit => Ok(Some(Message::Quit)),
+        }
+    }
+
+    fn stop_recording(&mut self) {
+        let Some((name, len)) = self.macros.stop() else {
+            self.notify(Level::Warn, "no macro is being recorded");
+            return;
+        };
+
+        self.notify(
+            Level::Info,
+            &format!("recorded macro '{name}' ({len} keys)"),
+        );
+        if shared::get(|shared| shared.config.macros.persist) {
+            if let Err(error) = self.macros.save() {
+                self.notify(Level::Error, &format!("{error:#}"));
+            }
+        }
+    }
+
+    fn play(&mut self, text: &str) -> Res<Option<Message>> {
+        if self.playing >= MAX_MACRO_DEPTH {
+            self.notify(Level::Error, "macros are nested too deeply");
+            return Ok(None);
+        }
+        let (count, keys) = match self.macros.invoke(text) {
+            Ok(invocation) => invocation,
+            Err(error) => {
+                self.notify(Level::Error, &format!("{error:#}"));
+                return Ok(None);
+            }
+        };
+
+        if self.playing == 0 {
+            self.replay_budget = Some(MAX_REPLAYED_KEYS);
+        }
+        self.playing += 1;
+        let result = self.replay(&keys, count);
+        self.playing -= 1;
+
+        result
+    }
+
+    fn replay(&mut self, keys: &[KeyCombo], count: usize) -> Res<Option<Message>> {
+        for &combo in iter::repeat_n(keys, count).flatten() {
+            // The budget is shared by nested macros and set to `None` once the replay is aborted.
+            let Some(budget) = &mut self.replay_budget else {
+                return Ok(None);
+            };
+            if *budget == 0 {
+                self.replay_budget = None;
+                let text = format!("macro stopped after replaying {MAX_REPLAYED_KEYS} keys");
+                self.notify(Level::Error, &text);
+                return Ok(None);
+            }
+            *budget -= 1;
+
+            let mut message = Message::Input(Input::KeyCombo(combo));
+            while let Some(returned) = self.update(&message)? {
+                message = match returned {
+                    Message::Input(_) => {
+                        self.replay_budget = None;
+                        self.notify(Level::Error, "macro stopped: a key produced more input");
+                        return Ok(None);
+                    }
+                    Message::Quit => return Ok(Some(Message::Quit)),
+                    other => other,
+                };
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn cancel_chord(&mut self) {
+        self.chord.clear();
+        self.chord_expires = None;
+        self.bottom.pending = None;
+    }
+
+    fn press(&mut self, combo: KeyCombo) -> Option<Option<Message>> {
+        let cancel = match combo.key {
+            Key::Esc => true,
+            Key::Char('g') => combo.ctrl && !combo.alt,
+            _ => false,
+        };
+        if !self.chord.is_empty() && cancel {
+            self.cancel_chord();
+            return Some(None);
+        }
+
+        self.chord.push(combo);
+        let context = self.context();
+
+        match shared::get(|shared| shared.keymap.resolve(context, &self.chord)) {
+            Resolution::Command(command) => {
+                self.cancel_chord();
+                Some(Some(Message::Command(command)))
+            }
+            Resolution::Pending => {
+                self.chord_expires = Some(Instant::now() + CHORD_TIMEOUT);
+                self.bottom.pending = Some(format!("{} …", keymap::display_sequence(&self.chord)));
+                Some(None)
+            }
+            Resolution::Unbound if self.chord.len() > 1 => {
+                let text = format!("{} is not bound", keymap::display_sequence(&self.chord));
+                self.cancel_chord();
+                Some(Some(Message::notify(Level::Warn, text)))
+            }
+            Resolution::Unbound => {
+                self.cancel_chord();
+                None
+            }
+        }
     }
 
     pub fn update(&mut self, message: &Message) -> Res<Option<Message>> {
+        if let Message::Input(Input::KeyCombo(combo)) = message {
+            if self.playing == 0 {
+                let idle =
+                    self.chord.is_empty() && self.palette.is_none() && self.minibuffer.is_none();
+                self.macros.record(*combo, idle);
+            }
+            // The leave dialog reads keys itself, so a bound key such as Ctrl-C cannot reach a
+            // command that would reopen or answer it.
+            if self.leaving.is_none() {
+                if let Some(update) = self.press(*combo) {
+                    return Ok(update);
+                }
+            }
+        }
+
+        if let (Some((dialog, leave)), Message::Input(_)) = (&mut self.leaving, message) {
+            let leave = *leave;
+            let choice = match message {
+                pressed!(Key::Esc) | pressed!(Key::Char('c'), ctrl) => Some('c'),
+                _ => dialog.update(message),
+            };
+            if let Some(choice) = choice {
+                self.leaving = None;
+                return self.resolve_leave(leave, choice);
+            }
+
+            return Ok(None);
+        }
+
+        if let (Some(minibuffer), Message::Input(_)) = (&mut self.minibuffer, message) {
+            if let Some(update) = minibuffer.update(message)? {
+                self.minibuffer = None;
+                if let Some(Message::PromptResult { kind, text }) = &update {
+                    self.remember(*kind, text);
+                }
+
+                return Ok(update);
+            }
+
+            return Ok(None);
+        }
+
+        if let (Some(palette), Message::Input(_)) = (&mut self.palette, message) {
+            if let Some(update) = palette.update(message) {
+                self.palette = None;
+                return Ok(update);
+            }
+
+            return Ok(None);
+        }
+
         let update = match message {
-            pressed!(Key::Char('c' | 'x'), ctrl) => Some(Message::Quit),
+            Message::Command(Command::Quit) => return self.leave(Leave::Quit),
+            Message::Command(Command::Close) => return self.leave(Leave::Close),
+            Message::Command(Command::Messages) => {
+                self.history = match self.history {
+                    Some(_) => None,
+                    None => Some(0),
+                };
+
+                return Ok(None);
+            }
+            Message::Command(Command::Palette) => {
+                self.palette = match self.palette {
+                    Some(_) => None,
+                    None => Some(Palette::new(self.context(), self.history_bounds)),
+                };
+
+                return Ok(None);
+            }
+            Message::Command(Command::ReloadConfig) => {
+                let errors = core::load_settings();
+                self.window.broadcast(message)?;
+                if errors.is_empty() {
+                    self.notify(Level::Info, "configuration reloaded");
+                }
+                for error in errors {
+                    self.notify(Level::Error, &error);
+                }
+                self.refresh()?;
+
+                return Ok(None);
+            }
+            Message::Command(Command::MacroRecord | Command::MacroStop) if self.playing > 0 => {
+                return Ok(None);
+            }
+            Message::Command(Command::MacroRecord) => {
+                match self.macros.recording() {
+                    Some(name) => {
+                        let text = format!("already recording macro '{name}'");
+                        self.notify(Level::Warn, &text);
+                    }
+                    None => {
+                        let last = self.macros.last().unwrap_or_default().to_owned();
+                        self.prompt(PromptKind::MacroRecord, &last, None)?;
+                    }
+                }
+
+                return Ok(None);
+            }
+            Message::Command(Command::MacroStop) => {
+                self.stop_recording();
+                self.refresh()?;
+
+                return Ok(None);
+            }
+            Message::Command(Command::MacroPlay) => {
+                let last = self.macros.last().unwrap_or_default().to_owned();
+                self.prompt(PromptKind::MacroPlay, &last, None)?;
+
+                return Ok(None);
+            }
+            Message::PromptResult {
+                kind: PromptKind::MacroRecord,
+                text,
+            } => {
+                match macros::parse_name(text) {
+                    Ok(name) => {
+                        self.macros.start(name);
+                        self.notify(Level::Info, &format!("recording macro '{name}'"));
+                    }
+                    Err(error) => self.notify(Level::Error, &format!("{error:#}")),
+                }
+                self.refresh()?;
+
+                return Ok(None);
+            }
+            Message::PromptResult {
+                kind: PromptKind::MacroPlay,
+                text,
+            } => return self.play(text),
+            Message::Prompt {
+                kind,
+                text,
+                complete,
+            } => {
+                self.prompt(*kind, text, *complete)?;
+
+                return Ok(None);
+            }
+            Message::Notify { level, text } => {
+                self.notify(*level, text);
+
+                return Ok(None);
+            }
+            Message::Input(_) if self.history.is_some() => {
+                self.scroll_history(message);
+
+                return Ok(None);
+            }
             _ => None,
         };
 
@@ -37,22 +534,90 @@ impl Frame {
             Ok(update)
         } else {
             let update = self.window.update(message)?;
-            self.top.update(&self.window)?;
-            self.bottom.update(&self.window)?;
+            self.refresh()?;
 
             Ok(update)
         }
     }
 
+    pub fn tick(&mut self) -> Res<bool> {
+        let mut notices = vec![];
+        let updated = self.window.tick(&mut notices)?;
+        if updated {
+            self.refresh()?;
+        }
+        for notice in &notices {
+            self.update(notice)?;
+        }
+
+        let expired = self
+            .expires
+            .is_some_and(|expires| expires <= Instant::now());
+        if expired {
+            self.expires = None;
+            self.bottom.notice = None;
+        }
+
+        let chord_expired = self
+            .chord_expires
+            .is_some_and(|expires| expires <= Instant::now());
+        if chord_expired {
+            self.cancel_chord();
+        }
+
+        Ok(updated || !notices.is_empty() || expired || chord_expired)
+    }
+
+    pub fn portals(&self) -> impl Iterator<Item = &Portal> {
+        self.window.portals()
+    }
+
+    pub fn contents(&self) -> impl Iterator<Item = &Content> {
+        self.window.contents()
+    }
+
     pub fn view(&self, out: &mut Out) -> Res {
         self.top.view(out)?;
         self.bottom.view(out)?;
-        self.window.view(out)
+        self.window.view(out)?;
+
+        if let Some(scroll) = self.history {
+            let records: Vec<_> = self.notifications.iter().cloned().collect();
+            logviewer::view_records(out, self.history_bounds, &records, scroll)?;
+        }
+        if let Some(palette) = &self.palette {
+            palette.view(out)?;
+        }
+        if let Some((dialog, _)) = &self.leaving {
+            dialog.view(out, self.history_bounds)?;
+        }
+        if let Some(minibuffer) = &self.minibuffer {
+            minibuffer.view(out)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct StatusFields(HashMap<&'static str, String>);
+
+impl StatusFields {
+    pub fn set(&mut self, name: &'static str, value: impl fmt::Display) -> Res {
+        let field = self.0.entry(name).or_default();
+        field.clear();
+        write!(field, "{value}")?;
+
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
     }
 }
 
 #[derive(Debug)]
-pub enum StatusLine {
+enum StatusLine {
     Top(String, String, String),
     Bottom(String, String, String),
 }
@@ -66,11 +631,19 @@ impl StatusLine {
         Self::Bottom("".into(), "".into(), "".into())
     }
 
-    fn clear(&mut self) {
+    fn render(&mut self, fields: &StatusFields) {
+        let template = shared::get(|shared| match self {
+            Self::Top(..) => shared.config.statusline.top.clone(),
+            Self::Bottom(..) => shared.config.statusline.bottom.clone(),
+        });
         let (Self::Top(left, middle, right) | Self::Bottom(left, middle, right)) = self;
-        left.clear();
-        middle.clear();
-        right.clear();
+
+        for (slot, template) in [left, middle, right]
+            .into_iter()
+            .zip(template::slots(&template))
+        {
+            *slot = template::render(template, |name| fields.get(name));
+        }
     }
 
     fn unwrap(&self) -> [&str; 3] {
@@ -83,30 +656,52 @@ impl StatusLine {
 struct StatusBar {
     bounds: Bounds,
     line: StatusLine,
+    notice: Option<Record>,
+    pending: Option<String>,
 }
 
 impl StatusBar {
-    fn new(bounds: Bounds, mut line: StatusLine, window: &Window) -> Res<Self> {
-        window.status(&mut line)?;
-
-        Ok(Self { bounds, line })
-    }
-
-    fn update(&mut self, window: &Window) -> Res {
-        self.line.clear();
-        window.status(&mut self.line)?;
-
-        Ok(())
+    fn new(bounds: Bounds, line: StatusLine) -> Self {
+        Self {
+            bounds,
+            line,
+            notice: None,
+            pending: None,
+        }
     }
 
     fn view(&self, out: &mut Out) -> Res {
         out::with_highlighted(out, |out| {
             out::clear(out, self.bounds)?;
 
-            for (status, bounds) in self.line.unwrap().iter().zip(self.bounds.vsplit3()) {
+            let width = self.bounds.width().into();
+
+            if let Some(pending) = &self.pending {
+                out::anchor(out, self.bounds)?;
+                queue!(out, Print(truncate(pending, width, true)))?;
+
+                return Ok(out);
+            }
+
+            if let Some(notice) = &self.notice {
+                if let color @ (Color::Red | Color::Yellow) = logviewer::level_color(notice.level) {
+                    queue!(out, SetBackgroundColor(color))?;
+                    out::clear(out, self.bounds)?;
+                }
+
+                out::anchor(out, self.bounds)?;
+                queue!(out, Print(truncate(&notice.message, width, false)))?;
+
+                return Ok(out);
+            }
+
+            let slots = self.line.unwrap().into_iter().zip(self.bounds.vsplit3());
+            for (i, (status, bounds)) in slots.enumerate() {
                 out::anchor(out, bounds)?;
 
-                let indent = (bounds.width() - u16::try_from(status.len())?) / 2;
+                let width = bounds.width().into();
+                let status = truncate(status, width, i == 0);
+                let indent = u16::try_from(width.saturating_sub(line::display_width(&status)) / 2)?;
 
                 queue!(out, MoveRight(indent), Print(status))?;
             }
@@ -117,3 +712,45 @@ impl StatusBar {
         Ok(())
     }
 }
+
+fn truncate(text: &str, width: usize, keep_end: bool) -> String {
+    if line::display_width(text) <= width {
+        return text.into();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    // Keep whole characters in the columns left beside the ellipsis.
+    let mut room = width - 1;
+    let fits = |&c: &char| {
+        let fits = char_display_width(c) <= room;
+        room = room.saturating_sub(char_display_width(c));
+        fits
+    };
+
+    if keep_end {
+        let kept: Vec<_> = text.chars().rev().take_while(fits).collect();
+        iter::once('…').chain(kept.into_iter().rev()).collect()
+    } else {
+        text.chars()
+            .take_while(fits)
+            .chain(iter::once('…'))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_by_display_width() {
+        assert_eq!(truncate("日本語", 6, false), "日本語");
+        assert_eq!(truncate("日本語", 5, false), "日本…");
+        assert_eq!(truncate("日本語", 4, false), "日…");
+        assert_eq!(truncate("日本語", 4, true), "…語");
+        assert_eq!(truncate("abc", 0, true), "");
+        assert_eq!(line::display_width("e\u{301}"), 1);
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{
+    command::Command,
+    component::frame::StatusFields,
+    core::Res,
+    message::{Input, Key, KeyCombo, Message},
+    utils::{
+        log::{self, Level},
+        out::{self, Bounds, Out},
+        shared,
+    },
+};
+use crossterm::{
+    cursor::{Hide, MoveDown, MoveToColumn},
+    queue,
+    style::{Print, PrintStyledContent, Stylize},
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const ROW_LENS: [usize; 4] = [16, 8, 4, 1];
+const OFFSET_WIDTH: usize = 8;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Column {
+    Hex,
+    Ascii,
+}
+
+#[derive(Clone, Debug)]
+pub struct Hex {
+    path: PathBuf,
+    bytes: Vec<u8>,
+    cursor: usize,
+    low_nibble: bool,
+    column: Column,
+    scroll: usize,
+    readonly: bool,
+    dirty: bool,
+    bounds: Bounds,
+}
+
+impl Hex {
+    pub fn open(path: impl AsRef<Path>, bounds: Bounds) -> Res<Self> {
+        log::debug!("opening {} as binary", path.as_ref().display());
+
+        Ok(Self {
+            path: path.as_ref().into(),
+            bytes: fs::read(path)?,
+            cursor: 0,
+            low_nibble: false,
+            column: Column::Hex,
+            scroll: 0,
+            readonly: false,
+            dirty: false,
+            bounds,
+        })
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    pub fn set_bounds(&mut self, bounds: Bounds) {
+        self.bounds = bounds;
+        self.move_to(self.cursor);
+    }
+
+    pub fn set_readonly(&mut self, readonly: bool) {
+        self.readonly = readonly;
+    }
+
+    fn row_len(&self) -> usize {
+        let width = usize::from(self.bounds.width());
+
+        ROW_LENS
+            .into_iter()
+            .find(|row_len| OFFSET_WIDTH + 2 + row_len * 4 < width)
+            .unwrap_or(1)
+    }
+
+    fn height(&self) -> usize {
+        self.bounds.height().into()
+    }
+
+    fn move_to(&mut self, cursor: usize) {
+        self.cursor = cursor.min(self.bytes.len().saturating_sub(1));
+        self.low_nibble = false;
+
+        let row = self.cursor / self.row_len();
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + self.height() {
+            self.scroll = row + 1 - self.height();
+        }
+    }
+
+    fn scroll_by(&mut self, rows: isize) {
+        let last_row = self.bytes.len().saturating_sub(1) / self.row_len();
+
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(rows)
+            .min(last_row.saturating_sub(self.height().saturating_sub(1)));
+    }
+
+    fn overwrite(&mut self, c: char) -> bool {
+        if self.bytes.is_empty() {
+            return false;
+        }
+
+        let byte = &mut self.bytes[self.cursor];
+        match self.column {
+            Column::Hex => {
+                let Some(digit) = c.to_digit(16) else {
+                    return false;
+                };
+                let digit = digit as u8;
+
+                if self.low_nibble {
+                    *byte = (*byte & 0xF0) | digit;
+                    self.move_to(self.cursor + 1);
+                } else {
+                    *byte = (*byte & 0x0F) | (digit << 4);
+                    self.low_nibble = true;
+                }
+            }
+
+            Column::Ascii => {
+                if !c.is_ascii() || c.is_ascii_control() {
+                    return false;
+                }
+
+                *byte = c as u8;
+                self.move_to(self.cursor + 1);
+            }
+        }
+        self.dirty = true;
+
+        true
+    }
+
+    fn read_only_notice(&self) -> Option<Message> {
+        Some(Message::notify(
+            Level::Warn,
+            format!("{} is read-only", self.name()),
+        ))
+    }
+
+    pub fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn discard(&mut self) {
+        self.dirty = false;
+    }
+
+    pub fn write_to(&self, mut writer: impl io::Write) -> Res {
+        writer.write_all(&self.bytes)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn save(&mut self) -> Res {
+        fs::write(&self.path, &self.bytes)?;
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    pub fn update(&mut self, message: &Message) -> Res<Option<Message>> {
+        let row_len = self.row_len();
+        let page = self.height() * row_len;
+        let scroll_dist = shared::get(|shared| shared.config.editor.scroll_dist);
+        let row_start = self.cursor - self.cursor % row_len;
+
+        match message {
+            Message::Command(Command::Save) => {
+                if self.readonly {
+                    return Ok(self.read_only_notice());
+                }
+
+                return match self.save() {
+                    Ok(()) => Ok(Some(Message::notify(
+                        Level::Info,
+                        format!("saved {}", self.path.display()),
+                    ))),
+                    Err(error) => match error.downcast_ref::<io::Error>() {
+                        Some(io_error) => Ok(Some(Message::notify(
+                            Level::Error,
+                            format!("cannot save {}: {io_error}", self.path.display()),
+                        ))),
+                        None => Err(error),
+                    },
+                };
+            }
+
+            Message::Command(Command::Tab) => {
+                self.column = match self.column {
+                    Column::Hex => Column::Ascii,
+                    Column::Ascii => Column::Hex,
+                };
+                self.low_nibble = false;
+            }
+
+            Message::Command(command) => match command {
+                Command::Top => self.move_to(0),
+                Command::Bottom => self.move_to(usize::MAX),
+                Command::CursorLeft => self.move_to(self.cursor.saturating_sub(1)),
+                Command::CursorRight => self.move_to(self.cursor + 1),
+                Command::CursorUp => self.move_to(self.cursor.saturating_sub(row_len)),
+                Command::CursorDown => self.move_to(self.cursor.saturating_add(row_len)),
+                Command::PageUp => self.move_to(self.cursor.saturating_sub(page)),
+                Command::PageDown => self.move_to(self.cursor.saturating_add(page)),
+                Command::LineStart => self.move_to(row_start),
+                Command::LineEnd => self.move_to(row_start + row_len - 1),
+                _ => {}
+            },
+
+            Message::Input(Input::ScrollUp) => self.scroll_by(-(scroll_dist as isize)),
+            Message::Input(Input::ScrollDown) => self.scroll_by(scroll_dist as isize),
+
+            Message::Input(Input::KeyCombo(KeyCombo {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+                ..
+            })) => {
+                if self.readonly {
+                    return Ok(self.read_only_notice());
+                }
+                self.overwrite(*c);
+            }
+
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str());
+        let column = match self.column {
+            Column::Hex => "hex",
+            Column::Ascii => "ascii",
+        };
+
+        fields.set("path", self.path.display())?;
+        fields.set("name", name.to_string_lossy())?;
+        fields.set("dirty", if self.dirty { " [+]" } else { "" })?;
+        match self.readonly {
+            true => fields.set("mode", format_args!("{column} read-only"))?,
+            false => fields.set("mode", column)?,
+        }
+        fields.set("offset", format_args!("0x{:08x}", self.cursor))?;
+        fields.set("position", format_args!("0x{:08x}", self.cursor))?;
+        fields.set("size", format_args!("{} bytes", self.bytes.len()))?;
+        if let Some(byte) = self.bytes.get(self.cursor) {
+            fields.set("format", format_args!("{byte} 0x{byte:02x} 0b{byte:08b}"))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn view(&self, out: &mut Out, active: bool) -> Res {
+        queue!(out, Hide)?;
+        out::clear(out, self.bounds)?;
+        out::anchor(out, self.bounds)?;
+
+        let row_len = self.row_len();
+
+        for row in (self.scroll..).take(self.height()) {
+            let start = row * row_len;
+            if start >= self.bytes.len() && !(start == 0 && self.bytes.is_empty()) {
+                break;
+            }
+
+            let end = (start + row_len).min(self.bytes.len());
+            let highlight =
+                |offset, column| active && offset == self.cursor && column == self.column;
+
+            queue!(out, Print(format_args!("{start:0OFFSET_WIDTH$x}  ")))?;
+
+            for offset in start..start + row_len {
+                let cell = match self.bytes.get(offset) {
+                    Some(byte) => format!("{byte:02x}"),
+                    None => "  ".into(),
+                };
+
+                if highlight(offset, Column::Hex) {
+                    queue!(out, PrintStyledContent(cell.reverse()), Print(' '))?;
+                } else {
+                    queue!(out, Print(cell), Print(' '))?;
+                }
+            }
+
+            queue!(out, Print(' '))?;
+
+            for (offset, &byte) in (start..end).zip(&self.bytes[start..end]) {
+                let c = if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                };
+
+                if highlight(offset, Column::Ascii) {
+                    queue!(out, PrintStyledContent(c.reverse()))?;
+                } else {
+                    queue!(out, Print(c))?;
+                }
+            }
+
+            queue!(out, MoveDown(1), MoveToColumn(self.bounds.x0))?;
+        }
+
+        Ok(())
+    }
+}
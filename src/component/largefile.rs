@@ -0,0 +1,653 @@
+use crate::{
+    command::Command,
+    component::frame::StatusFields,
+    core::Res,
+    message::{Input, Message, PromptKind},
+    utils::{
+        log::{self, Level},
+        out::{self, Bounds, Out},
+        shared,
+    },
+};
+use crossterm::{
+    cursor::{Hide, MoveDown, MoveToColumn},
+    queue,
+    style::{self, Print, PrintStyledContent, Stylize},
+};
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind},
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex, MutexGuard, PoisonError, Weak,
+    },
+    thread,
+};
+
+const INDEX_CHUNK: usize = 1 << 20;
+const MAX_LINE_BYTES: usize = 1 << 16;
+
+/// The file contents, read on demand rather than memory-mapped: a mapping turns a file that is
+/// truncated or rotated while it is open into SIGBUS, whereas reads just come back short.
+#[derive(Debug)]
+struct Data {
+    file: File,
+    len: usize,
+}
+
+impl Data {
+    fn open(path: &Path) -> Res<Self> {
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())?;
+
+        Ok(Self { file, len })
+    }
+
+    /// Reads `range`, returning fewer bytes if the file has shrunk since it was opened.
+    fn read(&self, range: Range<usize>) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; range.len()];
+        let mut filled = 0;
+
+        while filled < bytes.len() {
+            match read_at(
+                &self.file,
+                &mut bytes[filled..],
+                (range.start + filled) as u64,
+            ) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        bytes.truncate(filled);
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, bytes: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, bytes, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, bytes: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, bytes, offset)
+}
+
+#[derive(Debug)]
+struct Index {
+    starts: Vec<usize>,
+    scanned: usize,
+    done: bool,
+    failed: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Search {
+    query: String,
+    result: Arc<Mutex<Receiver<Option<usize>>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Search {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LargeFile {
+    path: PathBuf,
+    data: Arc<Data>,
+    index: Arc<Mutex<Index>>,
+    progress: (usize, bool),
+    top: usize,
+    last_query: Option<String>,
+    search: Option<Search>,
+    jump: Option<usize>,
+    follow: bool,
+    pinned: bool,
+    bounds: Bounds,
+}
+
+impl LargeFile {
+    pub fn open(path: impl AsRef<Path>, bounds: Bounds) -> Res<Self> {
+        let data = Arc::new(Data::open(path.as_ref())?);
+        let index = Arc::new(Mutex::new(Index {
+            starts: vec![0],
+            scanned: 0,
+            done: false,
+            failed: false,
+        }));
+
+        log::info!(
+            "opening {} ({} bytes) in large file mode",
+            path.as_ref().display(),
+            data.len
+        );
+
+        spawn_indexer(data.clone(), 0, Arc::downgrade(&index));
+
+        Ok(Self {
+            path: path.as_ref().into(),
+            data,
+            index,
+            progress: (0, false),
+            top: 0,
+            last_query: None,
+            search: None,
+            jump: None,
+            follow: false,
+            pinned: false,
+            bounds,
+        })
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    pub fn set_bounds(&mut self, bounds: Bounds) -> Res {
+        self.bounds = bounds;
+        let top = if self.pinned { usize::MAX } else { self.top };
+
+        self.scroll_to(top)
+    }
+
+    pub fn set_follow(&mut self, follow: bool) {
+        if follow {
+            log::info!("following {}", self.path.display());
+        }
+        self.follow = follow;
+        self.pinned = follow;
+    }
+
+    fn height(&self) -> usize {
+        self.bounds.height().into()
+    }
+
+    fn index(&self) -> Res<MutexGuard<'_, Index>> {
+        Ok(self.index.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn line_count(&self) -> Res<usize> {
+        Ok(self.index()?.starts.len())
+    }
+
+    fn scroll_to(&mut self, line: usize) -> Res {
+        let max = self.line_count()?.saturating_sub(self.height());
+        self.top = line.min(max);
+        self.pinned = self.top == max;
+
+        Ok(())
+    }
+
+    fn scroll_by(&mut self, lines: isize) -> Res {
+        self.scroll_to(self.top.saturating_add_signed(lines))
+    }
+
+    fn line(&self, index: &Index, line: usize) -> Option<String> {
+        let start = *index.starts.get(line)?;
+        let end = match index.starts.get(line + 1) {
+            Some(&next) => next - 1,
+            None if index.done => self.data.len,
+            None => return None,
+        };
+        let bytes = self
+            .data
+            .read(start..end.min(start + MAX_LINE_BYTES))
+            .unwrap_or_default();
+        let bytes = bytes.strip_suffix(b"\r").unwrap_or(&bytes);
+
+        Some(String::from_utf8_lossy(bytes).replace('\t', "    "))
+    }
+
+    fn search(&mut self) -> Res<Option<Message>> {
+        let Some(query) = self.last_query.clone().filter(|query| !query.is_empty()) else {
+            return Ok(None);
+        };
+
+        let from = match self.index()?.starts.get(self.top + 1) {
+            Some(&start) => start,
+            None => self.data.len,
+        };
+
+        self.cancel_search();
+        let (sender, result) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (data, needle, flag) = (
+            self.data.clone(),
+            query.clone().into_bytes(),
+            cancelled.clone(),
+        );
+        thread::spawn(move || {
+            let wrapped = 0..(from + needle.len() - 1).min(data.len);
+            let found = match find(&data, from..data.len, &needle, &flag) {
+                Some(None) => find(&data, wrapped, &needle, &flag),
+                found => found,
+            };
+            if let Some(found) = found {
+                let _ = sender.send(found);
+            }
+        });
+
+        self.search = Some(Search {
+            query,
+            result: Arc::new(Mutex::new(result)),
+            cancelled,
+        });
+
+        Ok(None)
+    }
+
+    fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.cancel();
+        }
+    }
+
+    pub fn cancel(&mut self) -> Option<Message> {
+        self.search.as_ref()?;
+        self.cancel_search();
+
+        Some(Message::notify(Level::Info, "search cancelled"))
+    }
+
+    fn poll_search(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        let Some(search) = &self.search else {
+            return Ok(false);
+        };
+
+        let received = search
+            .result
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_recv();
+        let found = match received {
+            Ok(found) => found,
+            Err(TryRecvError::Empty) => return Ok(false),
+            Err(TryRecvError::Disconnected) => {
+                notices.push(Message::notify(
+                    Level::Error,
+                    format!("searching for '{}' failed", search.query),
+                ));
+                None
+            }
+        };
+
+        match found {
+            Some(offset) => {
+                self.jump = Some(offset);
+                self.resolve_jump()?;
+            }
+            None => notices.push(Message::notify(
+                Level::Warn,
+                format!("'{}' not found", search.query),
+            )),
+        }
+        self.search = None;
+
+        Ok(true)
+    }
+
+    fn resolve_jump(&mut self) -> Res {
+        let Some(offset) = self.jump else {
+            return Ok(());
+        };
+
+        let line = {
+            let index = self.index()?;
+            if offset >= index.scanned && !index.done {
+                return Ok(());
+            }
+
+            index.starts.partition_point(|&start| start <= offset) - 1
+        };
+
+        self.jump = None;
+        self.top = line;
+        self.pinned = false;
+
+        Ok(())
+    }
+
+    fn grow(&mut self) -> Res {
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return Ok(());
+        };
+        let len = usize::try_from(metadata.len())?;
+        if len == self.data.len {
+            return Ok(());
+        }
+
+        let data = Arc::new(Data::open(&self.path)?);
+        let truncated = data.len < self.data.len;
+        let from = match truncated {
+            true => 0,
+            false => self.data.len.saturating_sub(1),
+        };
+        if truncated {
+            log::info!("{} was truncated or replaced", self.path.display());
+            self.top = 0;
+        }
+
+        {
+            let mut index = self.index()?;
+            index.done = false;
+            if truncated {
+                index.starts = vec![0];
+                index.scanned = 0;
+            }
+        }
+
+        self.data = data.clone();
+        spawn_indexer(data, from, Arc::downgrade(&self.index));
+
+        Ok(())
+    }
+
+    pub fn tick(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        let searched = self.poll_search(notices)?;
+        if self.follow && self.progress.1 && !self.index()?.failed {
+            self.grow()?;
+        }
+
+        let (progress, failed) = {
+            let index = self.index()?;
+            ((index.scanned, index.done), index.failed)
+        };
+
+        if progress == self.progress {
+            return Ok(searched);
+        }
+        if self.follow && self.pinned {
+            self.scroll_to(usize::MAX)?;
+        }
+
+        self.progress = progress;
+        if failed {
+            notices.push(Message::notify(
+                Level::Error,
+                format!(
+                    "indexing {} failed after {} lines",
+                    self.path.display(),
+                    self.line_count()?
+                ),
+            ));
+        } else if progress.1 {
+            log::info!(
+                "indexed {} lines of {}",
+                self.line_count()?,
+                self.path.display()
+            );
+        }
+        self.resolve_jump()?;
+
+        Ok(true)
+    }
+
+    pub fn update(&mut self, message: &Message) -> Res<Option<Message>> {
+        let page = self.height().saturating_sub(1) as isize;
+        let scroll_dist = shared::get(|shared| shared.config.editor.scroll_dist);
+
+        match message {
+            Message::Command(Command::SearchStart) => {
+                return Ok(Some(Message::Prompt {
+                    kind: PromptKind::Search,
+                    text: self.last_query.clone().unwrap_or_default(),
+                    complete: None,
+                }));
+            }
+            Message::PromptResult {
+                kind: PromptKind::Search,
+                text,
+            } => {
+                self.last_query = Some(text.clone());
+                return self.search();
+            }
+            Message::Command(Command::SearchNext) => return self.search(),
+            Message::Command(Command::Follow) => self.set_follow(!self.follow),
+            Message::Command(Command::Top) => self.scroll_to(0)?,
+            Message::Command(Command::Bottom) => self.scroll_to(usize::MAX)?,
+            Message::Command(Command::CursorUp) => self.scroll_by(-1)?,
+            Message::Command(Command::CursorDown) => self.scroll_by(1)?,
+            Message::Command(Command::PageUp) => self.scroll_by(-page)?,
+            Message::Command(Command::PageDown) => self.scroll_by(page)?,
+            Message::Input(Input::ScrollUp) => self.scroll_by(-(scroll_dist as isize))?,
+            Message::Input(Input::ScrollDown) => self.scroll_by(scroll_dist as isize)?,
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str());
+        let (scanned, done) = self.progress;
+        let lines = self.line_count()?;
+
+        fields.set("path", self.path.display())?;
+        fields.set("name", name.to_string_lossy())?;
+        fields.set(
+            "mode",
+            if self.follow {
+                "following"
+            } else {
+                "read-only"
+            },
+        )?;
+        fields.set("line", self.top + 1)?;
+        fields.set("lines", lines)?;
+        fields.set("format", "large file")?;
+        fields.set("position", format_args!("line {}", self.top + 1))?;
+        if done {
+            fields.set("size", format_args!("{lines} lines"))?;
+        } else {
+            let percent = scanned * 100 / self.data.len.max(1);
+            fields.set("size", format_args!("indexing {percent}%"))?;
+        }
+        if self.search.is_some() {
+            fields.set("format", "searching…")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn view(&self, out: &mut Out, _active: bool) -> Res {
+        queue!(out, Hide)?;
+        out::clear(out, self.bounds)?;
+        out::anchor(out, self.bounds)?;
+
+        let index = self.index()?;
+        let num_width = format!("{}", self.top + self.height()).len().max(3);
+        let width = usize::from(self.bounds.width()).saturating_sub(num_width + 1);
+        let dim = shared::get(|shared| shared.config.colors.dim);
+
+        for line in (self.top..).take(self.height()) {
+            let Some(text) = self.line(&index, line) else {
+                break;
+            };
+
+            queue!(
+                out,
+                PrintStyledContent(
+                    style::style(format_args!("{:num_width$} ", line + 1)).with(dim)
+                ),
+                Print(text.chars().take(width).collect::<String>()),
+                MoveDown(1),
+                MoveToColumn(self.bounds.x0),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn find(
+    data: &Data,
+    range: Range<usize>,
+    needle: &[u8],
+    cancelled: &AtomicBool,
+) -> Option<Option<usize>> {
+    let mut start = range.start;
+
+    while start < range.end {
+        if cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let end = (start + INDEX_CHUNK + needle.len() - 1).min(range.end);
+        let Ok(bytes) = data.read(start..end) else {
+            break;
+        };
+        if let Some(i) = bytes
+            .windows(needle.len())
+            .position(|window| window == needle)
+        {
+            return Some(Some(start + i));
+        }
+        if bytes.len() < end - start {
+            break;
+        }
+        start += INDEX_CHUNK;
+    }
+
+    Some(None)
+}
+
+fn spawn_indexer(data: Arc<Data>, from: usize, index: Weak<Mutex<Index>>) {
+    thread::spawn(move || {
+        let built = panic::catch_unwind(AssertUnwindSafe(|| build_index(&data, from, &index)));
+
+        if let (Err(_), Some(index)) = (built, index.upgrade()) {
+            let mut index = index.lock().unwrap_or_else(PoisonError::into_inner);
+            index.done = true;
+            index.failed = true;
+        }
+    });
+}
+
+fn build_index(data: &Data, from: usize, index: &Weak<Mutex<Index>>) {
+    let mut base = from;
+
+    while base < data.len {
+        let end = (base + INDEX_CHUNK).min(data.len);
+        let Ok(chunk) = data.read(base..end) else {
+            break;
+        };
+        let starts: Vec<_> = chunk
+            .iter()
+            .enumerate()
+            .filter(|&(j, &byte)| byte == b'\n' && base + j + 1 < data.len)
+            .map(|(j, _)| base + j + 1)
+            .collect();
+
+        let Some(index) = index.upgrade() else {
+            return;
+        };
+        let Ok(mut index) = index.lock() else {
+            return;
+        };
+        index.starts.extend(starts);
+        index.scanned = base + chunk.len();
+        if chunk.len() < end - base {
+            // The file shrank underneath us; whatever is left is indexed on the next `grow`.
+            break;
+        }
+        base = end;
+    }
+
+    if let Some(index) = index.upgrade() {
+        if let Ok(mut index) = index.lock() {
+            index.done = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process, time::Duration};
+
+    fn indexed(large: &LargeFile) {
+        while !large.index().unwrap().done {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn open(name: &str, text: &str) -> (PathBuf, LargeFile) {
+        let path = env::temp_dir().join(format!("neonano-{name}-{}.log", process::id()));
+        fs::write(&path, text).unwrap();
+        let bounds = Bounds {
+            x0: 0,
+            y0: 0,
+            x1: 80,
+            y1: 24,
+        };
+
+        let large = LargeFile::open(&path, bounds).unwrap();
+        indexed(&large);
+
+        (path, large)
+    }
+
+    #[test]
+    fn search_wraps_to_the_start() {
+        let text: String = (0..100).map(|i| format!("x{i}\n")).collect();
+        let (path, mut large) = open("wrap", &text);
+        large.scroll_to(50).unwrap();
+
+        let query = Message::PromptResult {
+            kind: PromptKind::Search,
+            text: "x3\n".into(),
+        };
+        large.update(&query).unwrap();
+        large.update(&Message::Command(Command::PageDown)).unwrap();
+        assert!(large.top > 50);
+        while large.search.is_some() {
+            large.tick(&mut vec![]).unwrap();
+        }
+        assert_eq!(large.top, 3);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn survives_truncation() {
+        let (path, mut large) = open("truncate", &"line\n".repeat(1000));
+        indexed(&large);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(12)
+            .unwrap();
+
+        {
+            let index = large.index().unwrap();
+            assert_eq!(large.line(&index, 1).as_deref(), Some("line"));
+            assert_eq!(large.line(&index, 2).as_deref(), Some("li"));
+            assert_eq!(large.line(&index, 999).as_deref(), Some(""));
+        }
+        let cancelled = AtomicBool::new(false);
+        let needle = b"line\nline\nline";
+        assert_eq!(
+            find(&large.data, 0..large.data.len, needle, &cancelled),
+            Some(None)
+        );
+
+        large.set_follow(true);
+        large.grow().unwrap();
+        indexed(&large);
+        assert_eq!(large.line_count().unwrap(), 3);
+        assert_eq!(large.top, 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
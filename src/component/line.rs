@@ -1,17 +1,44 @@
 use crate::{core::Res, utils::out::Out};
 use anyhow::Context;
 use crossterm::{
-    cursor::{EnableBlinking, MoveToColumn, Show},
+    cursor::{EnableBlinking, MoveToColumn, RestorePosition, SavePosition, Show},
     queue,
-    style::Print,
+    style::{Print, PrintStyledContent, Stylize},
 };
-use std::iter::{self, Once, Repeat, Take};
+use std::{
+    cell::Cell,
+    iter::{self, Once, Repeat, Take},
+    ops::Range,
+};
+use unicode_width::UnicodeWidthChar;
+
+const DEFAULT_TAB_SIZE: usize = 4;
+
+thread_local! {
+    static TAB_SIZE: Cell<usize> = const { Cell::new(DEFAULT_TAB_SIZE) };
+}
+
+pub fn with_tab_size<Ret>(tab_size: usize, f: impl FnOnce() -> Ret) -> Ret {
+    let previous = TAB_SIZE.replace(tab_size);
+    let ret = f();
+    TAB_SIZE.set(previous);
 
-const TAB_SIZE: usize = 4;
+    ret
+}
+
+/// Terminal columns taken by `c` outside the buffer: wide characters take two and combining marks
+/// none. Control characters count as one, for the control picture drawn in their place.
+pub fn char_display_width(c: char) -> usize {
+    c.width().unwrap_or(1)
+}
+
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_display_width).sum()
+}
 
 fn char_width(c: char) -> usize {
     match c {
-        '\t' => TAB_SIZE,
+        '\t' => TAB_SIZE.get(),
         _ => 1,
     }
 }
@@ -25,7 +52,13 @@ enum CharIter {
 impl CharIter {
     fn new(c: char) -> Self {
         match c {
-            '\t' => Self::Tab(iter::repeat(' ').take(TAB_SIZE)),
+            '\t' => Self::Tab(iter::repeat(' ').take(TAB_SIZE.get())),
+            // Stray control characters, like the carriage returns kept in mixed-ending files,
+            // are shown as control pictures so they cannot move the terminal cursor.
+            '\x7f' => Self::SingleChar(iter::once('\u{2421}')),
+            c if c.is_ascii_control() => Self::SingleChar(iter::once(
+                char::from_u32(0x2400 + u32::from(c)).unwrap_or(c),
+            )),
             _ => Self::SingleChar(iter::once(c)),
         }
     }
@@ -71,6 +104,16 @@ pub enum RawIndex {
     Invalid { display: usize },
 }
 
+impl Index {
+    pub fn display(self) -> usize {
+        self.display
+    }
+
+    pub fn byte(self) -> usize {
+        self.byte
+    }
+}
+
 impl RawIndex {
     pub fn index_front() -> Self {
         Self::Valid(Default::default())
@@ -218,6 +261,69 @@ impl Line {
         })
     }
 
+    fn is_word(&self, index: Index) -> Res<bool> {
+        Ok(self.get(index)?.is_some_and(char::is_alphanumeric))
+    }
+
+    pub fn index_next_word(&self, index: Index) -> Res<Option<Index>> {
+        let mut index = match self.index_forward_word(index)? {
+            Some(end) if self.is_word(index)? => end,
+            _ => index,
+        };
+
+        while !self.is_word(index)? {
+            match self.index_forward(index)? {
+                Some(next) => index = next,
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some(index))
+    }
+
+    pub fn index_first_word(&self) -> Res<Option<Index>> {
+        let front = Index::default();
+
+        match self.is_word(front)? {
+            true => Ok(Some(front)),
+            false => self.index_next_word(front),
+        }
+    }
+
+    pub fn index_word_end(&self, index: Index) -> Res<Option<Index>> {
+        let Some(next) = self.index_forward(index)? else {
+            return Ok(None);
+        };
+        let start = match self.is_word(next)? {
+            true => next,
+            false => match self.index_next_word(next)? {
+                Some(start) => start,
+                None => return Ok(None),
+            },
+        };
+        let after = self.index_forward_word(start)?.unwrap_or(start);
+
+        self.index_backward(after)
+    }
+
+    pub fn index_prev_word(&self, mut index: Index) -> Res<Option<Index>> {
+        loop {
+            match self.index_backward(index)? {
+                Some(previous) => index = previous,
+                None => return Ok(None),
+            }
+            if self.is_word(index)? {
+                break;
+            }
+        }
+
+        Ok(Some(match self.index_backward_word(index)? {
+            Some(before) if !self.is_word(before)? => self.index_forward(before)?.unwrap_or(before),
+            Some(before) => before,
+            None => index,
+        }))
+    }
+
     pub fn index_back(&self, index: RawIndex) -> Res<Index> {
         Ok(match index {
             RawIndex::Valid(index) => self.indices_from(index)?.last().unwrap_or(index),
@@ -231,6 +337,11 @@ impl Line {
         })
     }
 
+    pub fn trim_end(&mut self) {
+        let len = self.content.trim_end_matches([' ', '\t']).len();
+        self.content.truncate(len);
+    }
+
     pub fn clear(&mut self) {
         self.content.clear();
     }
@@ -273,4 +384,25 @@ impl Line {
 
         Ok(())
     }
+
+    pub fn highlight(&self, out: &mut Out, x0: u16, x1: u16, columns: Range<usize>) -> Res {
+        let width = usize::from(x1 - x0 - 1);
+        let columns = columns.start.min(width)..columns.end.min(width);
+        let selected: String = self
+            .chars()
+            .skip(columns.start)
+            .take(columns.len())
+            .collect();
+        let column = x0 + u16::try_from(columns.start)?;
+
+        queue!(
+            out,
+            SavePosition,
+            MoveToColumn(column),
+            PrintStyledContent(selected.reverse()),
+            RestorePosition,
+        )?;
+
+        Ok(())
+    }
 }
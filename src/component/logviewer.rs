@@ -0,0 +1,118 @@
+use crate::{
+    command::Command,
+    component::frame::StatusFields,
+    core::Res,
+    message::{Input, Message},
+    utils::{
+        log::{self, Level, Record},
+        out::{self, Bounds, Out},
+        shared,
+    },
+};
+use crossterm::{
+    cursor::{Hide, MoveDown, MoveToColumn},
+    queue,
+    style::{self, Color, PrintStyledContent, Stylize},
+};
+
+#[derive(Clone, Debug)]
+pub struct LogViewer {
+    scroll: usize,
+    bounds: Bounds,
+}
+
+impl LogViewer {
+    pub fn new(bounds: Bounds) -> Self {
+        Self { scroll: 0, bounds }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    pub fn set_bounds(&mut self, bounds: Bounds) {
+        self.bounds = bounds;
+    }
+
+    fn scroll_up(&mut self, by: usize) {
+        let max = log::records()
+            .len()
+            .saturating_sub(self.bounds.height().into());
+
+        self.scroll = (self.scroll + by).min(max);
+    }
+
+    fn scroll_down(&mut self, by: usize) {
+        self.scroll = self.scroll.saturating_sub(by);
+    }
+
+    pub fn update(&mut self, message: &Message) -> Res<Option<Message>> {
+        let page = usize::from(self.bounds.height()) / 2;
+        let scroll_dist = shared::get(|shared| shared.config.editor.scroll_dist);
+
+        match message {
+            Message::Command(Command::CursorUp) => self.scroll_up(1),
+            Message::Command(Command::CursorDown) => self.scroll_down(1),
+            Message::Command(Command::PageUp) => self.scroll_up(page),
+            Message::Command(Command::PageDown) => self.scroll_down(page),
+            Message::Command(Command::Top) => self.scroll_up(usize::MAX / 2),
+            Message::Command(Command::Bottom) => self.scroll = 0,
+            Message::Input(Input::ScrollUp) => self.scroll_up(scroll_dist),
+            Message::Input(Input::ScrollDown) => self.scroll_down(scroll_dist),
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
+        let records = log::records().len();
+
+        fields.set("path", "Log")?;
+        fields.set("name", "Log")?;
+        fields.set("lines", records)?;
+        fields.set("size", format_args!("{records} records"))?;
+        if self.scroll > 0 {
+            fields.set("position", format_args!("{} more below", self.scroll))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn view(&self, out: &mut Out, _active: bool) -> Res {
+        view_records(out, self.bounds, &log::records(), self.scroll)
+    }
+}
+
+pub fn level_color(level: Level) -> Color {
+    match level {
+        Level::Error => Color::Red,
+        Level::Warn => Color::Yellow,
+        Level::Info => Color::Reset,
+        Level::Debug | Level::Trace => Color::DarkGrey,
+    }
+}
+
+pub fn view_records(out: &mut Out, bounds: Bounds, records: &[Record], scroll: usize) -> Res {
+    queue!(out, Hide)?;
+    out::clear(out, bounds)?;
+    out::anchor(out, bounds)?;
+
+    let end = records.len().saturating_sub(scroll);
+    let start = end.saturating_sub(bounds.height().into());
+    let width = bounds.width().into();
+
+    for record in &records[start..end] {
+        let line = record.to_string();
+        let line: String = line.chars().take(width).collect();
+
+        queue!(
+            out,
+            PrintStyledContent(style::style(line).with(level_color(record.level))),
+            MoveDown(1),
+            MoveToColumn(bounds.x0),
+        )?;
+    }
+
+    Ok(())
+}
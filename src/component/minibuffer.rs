@@ -0,0 +1,217 @@
+use crate::{
+    component::line::{Index, Line, RawIndex},
+    core::Res,
+    message::{Completer, Input, Key, KeyCombo, Message, PromptKind},
+    pressed,
+    utils::{
+        complete,
+        out::{self, Bounds, Out},
+    },
+};
+use crossterm::{cursor::MoveTo, queue, style::Print};
+use std::path::MAIN_SEPARATOR;
+
+const MAX_CANDIDATES: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct Minibuffer {
+    kind: PromptKind,
+    complete: Option<Completer>,
+    line: Line,
+    index: Index,
+    history: Vec<String>,
+    browsing: Option<usize>,
+    draft: String,
+    candidates: Vec<String>,
+    bounds: Bounds,
+}
+
+impl Minibuffer {
+    pub fn new(
+        kind: PromptKind,
+        text: &str,
+        complete: Option<Completer>,
+        history: Vec<String>,
+        bounds: Bounds,
+    ) -> Res<Self> {
+        let mut minibuffer = Self {
+            kind,
+            complete,
+            line: Line::default(),
+            index: Index::default(),
+            history,
+            browsing: None,
+            draft: String::new(),
+            candidates: vec![],
+            bounds,
+        };
+        minibuffer.set_text(text)?;
+
+        Ok(minibuffer)
+    }
+
+    fn label(&self) -> &'static str {
+        match self.kind {
+            PromptKind::SaveAs => "save as: ",
+            PromptKind::Goto => "go to line: ",
+            PromptKind::Search => "search: ",
+            PromptKind::MacroRecord => "record macro: ",
+            PromptKind::MacroPlay => "play macro: ",
+        }
+    }
+
+    fn text(&self) -> &str {
+        self.line.as_ref()
+    }
+
+    fn set_text(&mut self, text: &str) -> Res {
+        self.line = Line::from(text.to_string());
+        self.index = self.line.index_back(RawIndex::index_front())?;
+
+        Ok(())
+    }
+
+    fn browse(&mut self, older: bool) -> Res {
+        let browsing = match (self.browsing, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => return Ok(()),
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) => (i + 1 < self.history.len()).then_some(i + 1),
+        };
+
+        if self.browsing.is_none() {
+            self.draft = self.text().into();
+        }
+        self.browsing = browsing;
+
+        let text = match browsing {
+            Some(i) => self.history[i].clone(),
+            None => self.draft.clone(),
+        };
+        self.set_text(&text)
+    }
+
+    fn tab(&mut self) -> Res {
+        let Some(complete) = self.complete else {
+            return Ok(());
+        };
+        let candidates = complete(self.text());
+
+        if let Some(prefix) = complete::common_prefix(&candidates) {
+            if prefix.len() > self.text().len() {
+                self.set_text(prefix)?;
+            }
+        }
+        self.candidates = match candidates.len() {
+            0 | 1 => vec![],
+            _ => candidates,
+        };
+
+        Ok(())
+    }
+
+    pub fn update(&mut self, message: &Message) -> Res<Option<Option<Message>>> {
+        let line = &self.line;
+
+        match message {
+            pressed!(Key::Esc) => return Ok(Some(None)),
+            pressed!(Key::Enter) => {
+                return Ok(Some(Some(Message::PromptResult {
+                    kind: self.kind,
+                    text: self.text().into(),
+                })));
+            }
+            pressed!(Key::Tab) => return self.tab().map(|()| None),
+            pressed!(Key::Up) => self.browse(true)?,
+            pressed!(Key::Down) => self.browse(false)?,
+            pressed!(Key::Left, ctrl) => {
+                self.index = line.index_backward_word(self.index)?.unwrap_or_default();
+            }
+            pressed!(Key::Right, ctrl) => {
+                self.index = match line.index_forward_word(self.index)? {
+                    Some(index) => index,
+                    None => line.index_back(self.index.into())?,
+                };
+            }
+            pressed!(Key::Left) => {
+                self.index = line.index_backward(self.index)?.unwrap_or(self.index);
+            }
+            pressed!(Key::Right) => {
+                self.index = line.index_forward(self.index)?.unwrap_or(self.index);
+            }
+            pressed!(Key::Home) => self.index = Index::default(),
+            pressed!(Key::End) => self.index = line.index_back(self.index.into())?,
+            pressed!(Key::Backspace) => {
+                if let Some(index) = line.index_backward(self.index)? {
+                    self.line.remove(index);
+                    self.index = index;
+                }
+            }
+            pressed!(Key::Delete) => {
+                if !line.at_back(self.index) {
+                    self.line.remove(self.index);
+                }
+            }
+            Message::Input(Input::KeyCombo(KeyCombo {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+                ..
+            })) => {
+                self.line.insert(self.index, *c);
+                self.index = self.line.index_forward(self.index)?.unwrap_or(self.index);
+            }
+            _ => return Ok(None),
+        }
+        self.candidates.clear();
+
+        Ok(None)
+    }
+
+    pub fn view(&self, out: &mut Out) -> Res {
+        let Bounds { x0, y0, x1, .. } = self.bounds;
+        let label = self.label();
+        let hint = match self.candidates.as_slice() {
+            [] => String::new(),
+            candidates => {
+                let mut shown: Vec<_> = candidates
+                    .iter()
+                    .take(MAX_CANDIDATES)
+                    .map(|candidate| {
+                        candidate
+                            .rsplit(MAIN_SEPARATOR)
+                            .find(|part| !part.is_empty())
+                    })
+                    .map(Option::unwrap_or_default)
+                    .collect();
+                if candidates.len() > MAX_CANDIDATES {
+                    shown.push("…");
+                }
+
+                format!(" {{{}}}", shown.join(", "))
+            }
+        };
+
+        let start = x0 + u16::try_from(label.chars().count())?;
+        let end = x1
+            .saturating_sub(u16::try_from(hint.chars().count())?)
+            .max(start + 1);
+
+        out::with_highlighted(out, |out| {
+            out::clear(out, self.bounds)?;
+            out::anchor(out, self.bounds)?;
+            queue!(
+                out,
+                Print(label),
+                MoveTo(end, y0),
+                Print(&hint),
+                MoveTo(start, y0)
+            )?;
+            self.line.view(out, start, end, Some(self.index))?;
+
+            Ok(out)
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{
+    command::Command,
+    core::Res,
+    keymap::{self, Context},
+    message::{Input, Key, KeyCombo, Message},
+    pressed,
+    utils::{
+        fuzzy,
+        out::{self, Bounds, Out},
+        shared,
+    },
+};
+use crossterm::{
+    cursor::{Hide, MoveDown, MoveToColumn},
+    queue,
+    style::{self, Print, PrintStyledContent, Stylize},
+};
+
+const MAX_WIDTH: u16 = 60;
+const MAX_ROWS: usize = 12;
+
+#[derive(Clone, Debug)]
+pub struct Palette {
+    context: Context,
+    query: String,
+    matches: Vec<(Command, String)>,
+    selected: usize,
+    bounds: Bounds,
+}
+
+impl Palette {
+    pub fn new(context: Context, bounds: Bounds) -> Self {
+        let width = bounds.width().min(MAX_WIDTH);
+        let x0 = bounds.x0 + (bounds.width() - width) / 2;
+
+        let mut palette = Self {
+            context,
+            query: String::new(),
+            matches: vec![],
+            selected: 0,
+            bounds: Bounds {
+                x0,
+                x1: x0 + width,
+                ..bounds
+            },
+        };
+        palette.filter();
+
+        palette
+    }
+
+    fn filter(&mut self) {
+        let mut scored: Vec<_> = Command::ALL
+            .iter()
+            .filter(|&&command| command != Command::Palette)
+            .filter_map(|&command| {
+                fuzzy::score(&self.query, command.name()).map(|score| (score, command))
+            })
+            .collect();
+        scored.sort_by_key(|&(score, command)| (-score, command.name()));
+
+        self.matches = shared::get(|shared| {
+            scored
+                .into_iter()
+                .map(|(_, command)| {
+                    let binding = shared
+                        .keymap
+                        .binding(self.context, command)
+                        .map(keymap::display_sequence)
+                        .unwrap_or_default();
+
+                    (command, binding)
+                })
+                .collect()
+        });
+        self.selected = 0;
+    }
+
+    pub fn update(&mut self, message: &Message) -> Option<Option<Message>> {
+        match message {
+            pressed!(Key::Esc) => return Some(None),
+            pressed!(Key::Enter) => {
+                return self
+                    .matches
+                    .get(self.selected)
+                    .map(|&(command, _)| Some(Message::Command(command)));
+            }
+            pressed!(Key::Up) | Message::Input(Input::ScrollUp) => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            pressed!(Key::Down) | Message::Input(Input::ScrollDown) => {
+                self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+            }
+            pressed!(Key::Backspace) => {
+                self.query.pop();
+                self.filter();
+            }
+            Message::Input(Input::KeyCombo(KeyCombo {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+                ..
+            })) => {
+                self.query.push(*c);
+                self.filter();
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    pub fn view(&self, out: &mut Out) -> Res {
+        let rows = usize::from(self.bounds.height())
+            .saturating_sub(1)
+            .min(MAX_ROWS)
+            .min(self.matches.len().max(1));
+        let bounds = Bounds {
+            y1: self.bounds.y0 + u16::try_from(rows + 1)?,
+            ..self.bounds
+        };
+        let width = usize::from(bounds.width());
+        let first = self.selected.saturating_sub(rows.saturating_sub(1));
+
+        queue!(out, Hide)?;
+        out::with_highlighted(out, |out| {
+            out::clear(
+                out,
+                Bounds {
+                    y1: bounds.y0 + 1,
+                    ..bounds
+                },
+            )?;
+            out::anchor(out, bounds)?;
+
+            let prompt: String = format!("> {}", self.query).chars().take(width).collect();
+            queue!(out, Print(prompt), MoveDown(1), MoveToColumn(bounds.x0))?;
+
+            Ok(out)
+        })?;
+        out::clear(
+            out,
+            Bounds {
+                y0: bounds.y0 + 1,
+                ..bounds
+            },
+        )?;
+
+        if self.matches.is_empty() && rows > 0 {
+            queue!(
+                out,
+                PrintStyledContent(
+                    style::style("no matching commands")
+                        .with(shared::get(|shared| shared.config.colors.dim))
+                )
+            )?;
+        }
+
+        for (i, (command, binding)) in self.matches.iter().enumerate().skip(first).take(rows) {
+            let name = command.name();
+            let gap = width.saturating_sub(name.chars().count() + binding.chars().count());
+            let line: String = format!("{name}{:gap$}{binding}", "")
+                .chars()
+                .take(width)
+                .collect();
+
+            if i == self.selected {
+                queue!(out, PrintStyledContent(line.reverse()))?;
+            } else {
+                queue!(out, Print(line))?;
+            }
+            queue!(out, MoveDown(1), MoveToColumn(bounds.x0))?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,33 +1,169 @@
 use crate::{
+    args::Position,
+    command::Command,
     component::{
-        frame::StatusLine,
-        line::{Line, RawIndex},
+        dialog::Dialog,
+        frame::StatusFields,
+        line::{self, Index, Line, RawIndex},
     },
+    config::EditorConfig,
     core::Res,
-    message::{Input, Key, Message},
-    pressed,
-    utils::out::{self, Bounds, Out},
+    keymap,
+    message::{Input, Key, KeyCombo, Message, PromptKind},
+    utils::{
+        complete,
+        editorconfig::{self, Properties},
+        follow::{Event, Follower},
+        log::{self, Level},
+        out::{self, Bounds, Out},
+        shared,
+        swap::{self, Swap},
+        text::{self, Format},
+    },
+    vi::{Action, Insert, Mode, Motion, Operator, Parser, Register},
 };
 use anyhow::Context;
 use crossterm::{
-    cursor::{MoveDown, MoveToColumn, MoveToRow},
+    cursor::{MoveDown, MoveToColumn, MoveToRow, SetCursorStyle},
     queue,
     style::{self, Print, PrintStyledContent, Stylize},
 };
+use encoding_rs::UTF_8;
 use std::{
     cmp::Ordering,
-    collections::VecDeque,
-    fmt::Write,
-    fs::File,
-    io::{BufRead, BufReader},
-    path::Path,
+    collections::{hash_map::DefaultHasher, VecDeque},
+    error, fmt,
+    fs::{self, Metadata},
+    hash::{Hash, Hasher},
+    io::{self, BufWriter, ErrorKind, Read, Write as _},
+    mem,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
 };
 
-const SCROLL_GRACE: usize = 3;
-const SCROLL_DIST: usize = 5;
+const SWAP_INTERVAL: Duration = Duration::from_secs(2);
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_PASTE: usize = 1 << 20;
+
+#[derive(Clone, Debug)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+}
+
+#[derive(Debug)]
+pub struct Binary;
+
+impl fmt::Display for Binary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("file contains binary data")
+    }
+}
+
+impl error::Error for Binary {}
+
+#[derive(Clone, Debug)]
+enum Pending {
+    InUse,
+    Recover {
+        swap: PathBuf,
+        text: String,
+    },
+    Reload {
+        text: String,
+        format: Format,
+        stamp: Stamp,
+    },
+    Overwrite {
+        text: String,
+        format: Format,
+        stamp: Stamp,
+    },
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl Stamp {
+    fn new(bytes: &[u8], metadata: &Metadata) -> Self {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+
+        Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: hasher.finish(),
+        }
+    }
+
+    fn matches(&self, metadata: &Metadata) -> bool {
+        self.modified == metadata.modified().ok() && self.len == metadata.len()
+    }
+}
+
+// The mode bits alone miss ACLs, read-only mounts and the owner's privileges, so ask the system.
+#[cfg(unix)]
+fn is_writable(path: &Path) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a valid NUL-terminated string that outlives the call.
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_writable(path: &Path) -> bool {
+    fs::OpenOptions::new().write(true).open(path).is_ok()
+}
+
+#[derive(Clone, Debug)]
+struct Journal {
+    swap: Option<PathBuf>,
+    stale: bool,
+    written: Instant,
+}
+
+type Point = (usize, Index);
+
+#[derive(Copy, Clone, Debug)]
+enum Chain {
+    Kill,
+    Yank(Point),
+}
+
+#[derive(Clone, Default, Debug)]
+struct Modal {
+    mode: Mode,
+    parser: Parser,
+    anchor: Point,
+    change: Vec<Message>,
+    last_change: Vec<Message>,
+}
 
 #[derive(Clone, Debug)]
 pub struct Portal {
+    source: Source,
+    readonly: bool,
+    pager: bool,
+    follower: Option<Follower>,
+    last_query: Option<String>,
+    dirty: bool,
+    format: Format,
+    journal: Journal,
+    stamp: Option<Stamp>,
+    checked: Instant,
+    dialog: Option<(Dialog, Pending)>,
+    settings: EditorConfig,
+    editorconfig: Properties,
+    modal: Option<Modal>,
+    mark: Option<Point>,
+    chain: Option<Chain>,
     lines: VecDeque<Line>,
     above: String,
     below: String,
@@ -41,11 +177,96 @@ pub struct Portal {
 
 impl Portal {
     pub fn open(path: impl AsRef<Path>, bounds: Bounds) -> Res<Self> {
-        let height = bounds.height().into();
-        let file = BufReader::new(File::open(path)?);
-        let mut lines = file.lines().collect::<Result<Vec<_>, _>>()?;
-        let line_num_width = 3.max(format!("{}", lines.len()).len().try_into()?);
-        let below = (height < lines.len())
+        let source = Source::File(path.as_ref().into());
+
+        log::debug!("opening {}", path.as_ref().display());
+        let properties = editorconfig::resolve(path.as_ref());
+
+        let mut portal = match fs::read(path.as_ref()) {
+            Ok(bytes) => {
+                if text::is_binary(&bytes) {
+                    return Err(Binary.into());
+                }
+
+                // The file is read in whatever encoding it is in; `charset` only decides how it
+                // is written back.
+                let (lines, mut format) = text::decode(&bytes)?;
+                properties.apply(&mut format);
+                let metadata = fs::metadata(path.as_ref())?;
+                let mut portal = Self::new(source, lines, format, bounds)?;
+                portal.stamp = Some(Stamp::new(&bytes, &metadata));
+                portal.readonly = !is_writable(path.as_ref());
+
+                portal
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                let mut format = Format::default();
+                properties.apply(&mut format);
+
+                Self::new(source, vec![], format, bounds)?
+            }
+            Err(error) => return Err(error.into()),
+        };
+        portal.editorconfig = properties;
+        portal.configure();
+
+        Ok(portal)
+    }
+
+    pub fn stdin(bounds: Bounds) -> Res<Self> {
+        let mut bytes = vec![];
+        io::stdin().lock().read_to_end(&mut bytes)?;
+        let (lines, format) = text::decode(&bytes)?;
+
+        Self::new(Source::Stdin, lines, format, bounds)
+    }
+
+    fn new(source: Source, lines: Vec<String>, format: Format, bounds: Bounds) -> Res<Self> {
+        let mut portal = Self {
+            source,
+            readonly: false,
+            pager: false,
+            follower: None,
+            last_query: None,
+            dirty: false,
+            format,
+            journal: Journal {
+                swap: None,
+                stale: false,
+                written: Instant::now(),
+            },
+            stamp: None,
+            checked: Instant::now(),
+            dialog: None,
+            settings: EditorConfig::default(),
+            editorconfig: Properties::default(),
+            modal: None,
+            mark: None,
+            chain: None,
+            lines: VecDeque::new(),
+            above: String::new(),
+            below: String::new(),
+            active: 0,
+            index: RawIndex::index_front(),
+            offset: 0,
+            line_num_width: 0,
+            bounds,
+            recycle: vec![],
+        };
+        portal.configure();
+        portal.reset(lines)?;
+        portal.check_swap()?;
+
+        Ok(portal)
+    }
+
+    fn reset(&mut self, mut lines: Vec<String>) -> Res {
+        let height = self.bounds.height().into();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        self.line_num_width = 3.max(format!("{}", lines.len()).len().try_into()?);
+        self.below = (height < lines.len())
             .then(|| {
                 lines
                     .split_off(height)
@@ -55,210 +276,1642 @@ impl Portal {
                     .collect()
             })
             .unwrap_or_default();
+        self.lines = lines.into_iter().map(Into::into).collect();
+        self.above.clear();
+        self.mark = None;
+        self.active = 0;
+        self.index = RawIndex::index_front();
+        self.offset = 0;
 
-        Ok(Self {
-            lines: lines.into_iter().map(Into::into).collect(),
-            above: String::new(),
-            below,
-            active: 0,
-            index: RawIndex::index_front(),
-            offset: 0,
-            line_num_width,
-            bounds,
-            recycle: vec![],
-        })
+        Ok(())
+    }
+
+    fn load(&mut self, text: &str, format: Format) -> Res {
+        let (lines, split) = text::decode_as(text.as_bytes(), UTF_8, false)?;
+        self.format = Format {
+            encoding: format.encoding,
+            bom: format.bom,
+            ..split
+        };
+        self.editorconfig.apply(&mut self.format);
+
+        self.reset(lines)
+    }
+
+    fn check_swap(&mut self) -> Res {
+        let Source::File(path) = &self.source else {
+            return Ok(());
+        };
+        let Some(swap) = swap::find(path) else {
+            return Ok(());
+        };
+
+        let Swap { owner, text } = match swap::read(&swap) {
+            Ok(swap) => swap,
+            Err(error) => {
+                log::warning!("ignoring swap file: {error:#}");
+                return Ok(());
+            }
+        };
+        let current = self.text();
+
+        if owner.is_alive() {
+            log::info!("{} is in use by {owner}", swap.display());
+
+            let dialog = Dialog::new(
+                format!(
+                    "{} is already being edited by neonano {owner}\n(swap file {})",
+                    path.display(),
+                    swap.display()
+                ),
+                &[('o', "open read-only"), ('e', "edit anyway")],
+            );
+
+            self.dialog = Some((dialog, Pending::InUse));
+        } else if text == current {
+            swap::remove(&swap)?;
+        } else {
+            log::info!("found swap file {}", swap.display());
+
+            let dialog = Dialog::new(
+                format!(
+                    "{} has unsaved changes from an earlier session in\n{}",
+                    path.display(),
+                    swap.display()
+                ),
+                &[('r', "restore"), ('x', "discard")],
+            )
+            .with_diff(&current, &text);
+
+            self.dialog = Some((dialog, Pending::Recover { swap, text }));
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&mut self, pending: Pending, choice: char) -> Res {
+        match (pending, choice) {
+            (Pending::InUse, 'o') => self.readonly = true,
+
+            (Pending::InUse, _) => {}
+
+            (Pending::Recover { swap, text }, 'r') => {
+                self.load(&text, self.format)?;
+                self.dirty = true;
+                self.journal.swap = Some(swap);
+                log::info!("restored {} from its swap file", self.name());
+            }
+
+            (Pending::Recover { swap, .. }, _) => {
+                swap::remove(&swap)?;
+                log::info!("discarded swap file {}", swap.display());
+            }
+
+            (
+                Pending::Reload {
+                    text,
+                    format,
+                    stamp,
+                }
+                | Pending::Overwrite {
+                    text,
+                    format,
+                    stamp,
+                },
+                'r',
+            ) => {
+                self.load(&text, format)?;
+                self.stamp = Some(stamp);
+                self.dirty = false;
+                self.journal.stale = false;
+                if let Some(swap) = self.journal.swap.take() {
+                    swap::remove(&swap)?;
+                }
+                log::info!("reloaded {}", self.name());
+            }
+
+            (Pending::Reload { stamp, .. }, _) => {
+                self.stamp = Some(stamp);
+                self.dirty = true;
+                self.journal.stale = true;
+            }
+
+            (Pending::Overwrite { .. }, _) => {
+                self.write_file()?;
+                log::info!("overwrote {}", self.name());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn disk_change(&mut self) -> Res<Option<(String, Format, Stamp)>> {
+        let (Source::File(path), Some(stamp)) = (&self.source, self.stamp) else {
+            return Ok(None);
+        };
+        let Ok(metadata) = fs::metadata(path) else {
+            return Ok(None);
+        };
+        if stamp.matches(&metadata) {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(path)?;
+        let new_stamp = Stamp::new(&bytes, &metadata);
+        if new_stamp.hash == stamp.hash {
+            self.stamp = Some(new_stamp);
+            return Ok(None);
+        }
+
+        match text::decode(&bytes) {
+            Ok((lines, format)) => {
+                let text = text::join(lines.iter().map(String::as_str), format);
+
+                Ok(Some((text, format, new_stamp)))
+            }
+            Err(error) => {
+                log::warning!("{} changed on disk: {error}", path.display());
+                self.stamp = Some(new_stamp);
+                Ok(None)
+            }
+        }
+    }
+
+    fn check_disk(&mut self) -> Res<bool> {
+        self.checked = Instant::now();
+        if self.dialog.is_some() || self.follower.is_some() {
+            return Ok(false);
+        }
+
+        let Some((text, format, stamp)) = self.disk_change()? else {
+            return Ok(false);
+        };
+        log::info!("{} changed on disk", self.name());
+
+        let dialog = Dialog::new(
+            format!("{} was changed by another program", self.name()),
+            &[('r', "reload"), ('k', "keep mine")],
+        )
+        .with_diff(&self.text(), &text);
+        self.dialog = Some((
+            dialog,
+            Pending::Reload {
+                text,
+                format,
+                stamp,
+            },
+        ));
+
+        Ok(true)
+    }
+
+    fn configure(&mut self) -> bool {
+        let path = match &self.source {
+            Source::File(path) => Some(path.as_path()),
+            Source::Stdin => None,
+        };
+        let mut settings = shared::get(|shared| shared.config.editor_for(path));
+        settings.tab_size = self.editorconfig.tab_width().unwrap_or(settings.tab_size);
+        if settings == self.settings {
+            return false;
+        }
+
+        if settings.modal != self.modal.is_some() {
+            self.modal = settings.modal.then(Modal::default);
+        }
+        if settings.tab_size != self.settings.tab_size {
+            self.forget_points();
+        }
+        self.settings = settings;
+
+        true
+    }
+
+    fn forget_points(&mut self) {
+        self.index.invalidate();
+        self.mark = None;
+        self.chain = None;
+        if let Some(
+            modal @ Modal {
+                mode: Mode::Visual, ..
+            },
+        ) = &mut self.modal
+        {
+            modal.mode = Mode::Normal;
+        }
+    }
+
+    pub fn tick(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        line::with_tab_size(self.settings.tab_size, || self.poll(notices))
+    }
+
+    fn poll(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        if let Some(follower) = &self.follower {
+            let events = follower.events();
+            let updated = !events.is_empty();
+
+            for event in events {
+                match event {
+                    Event::Stopped(reason) => {
+                        self.follower = None;
+                        notices.push(Message::notify(
+                            Level::Error,
+                            format!("stopped following {}: {reason}", self.name()),
+                        ));
+                    }
+                    event => self.apply(event)?,
+                }
+            }
+
+            return Ok(updated);
+        }
+
+        if self.journal.stale && self.journal.written.elapsed() >= SWAP_INTERVAL {
+            self.write_swap();
+        }
+
+        if self.checked.elapsed() >= DISK_CHECK_INTERVAL {
+            self.check_disk()
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn write_swap(&mut self) {
+        let Source::File(path) = &self.source else {
+            return;
+        };
+
+        match swap::write(path, |writer| Ok(writer.write_all(self.text().as_bytes())?)) {
+            Ok(swap) => self.journal.swap = Some(swap),
+            Err(error) => log::warning!("failed to write swap file for {}: {error:#}", self.name()),
+        }
+        self.journal.stale = false;
+        self.journal.written = Instant::now();
     }
 
     pub fn bounds(&self) -> Bounds {
         self.bounds
     }
 
-    fn current_line(&self) -> Res<&Line> {
-        self.lines.get(self.active).context("active is valid")
+    pub fn set_bounds(&mut self, bounds: Bounds) -> Res {
+        self.bounds = bounds;
+
+        // Scroll the lines above the cursor out first, so the cursor stays on screen.
+        while self.active > 0 && self.active >= bounds.height().into() {
+            let line_to_above = self.lines.pop_front().context("at least one line")?;
+            self.insert_above(line_to_above);
+            self.offset += 1;
+            self.active -= 1;
+        }
+
+        self.fix_lines()
     }
 
-    fn current_line_mut(&mut self) -> Res<&mut Line> {
-        self.lines.get_mut(self.active).context("active is valid")
+    pub fn set_readonly(&mut self, readonly: bool) {
+        self.readonly = readonly;
+        self.pager &= readonly;
     }
 
-    fn at_top(&self) -> bool {
-        self.active == 0
+    pub fn set_pager(&mut self, pager: bool) {
+        self.pager = pager;
+        self.readonly |= pager;
     }
 
-    fn at_bottom(&self) -> bool {
-        self.active == self.lines.len() - 1
+    fn is_readonly(&self) -> bool {
+        self.readonly || self.follower.is_some()
     }
 
-    fn insert_below(&mut self, line: Line) {
-        self.below.push('\n');
-        self.below.push_str(line.as_ref());
-        self.recycle.push(line);
+    pub fn set_follow(&mut self, follow: bool) -> Res<Option<Message>> {
+        if !follow {
+            if self.follower.take().is_some() {
+                if let Source::File(path) = &self.source {
+                    let bytes = fs::read(path)?;
+                    self.stamp = Some(Stamp::new(&bytes, &fs::metadata(path)?));
+                }
+            }
+
+            return Ok(None);
+        }
+
+        let (Source::File(path), Some(stamp)) = (&self.source, self.stamp) else {
+            return Ok(Some(Message::notify(
+                Level::Warn,
+                format!("{} is not a file on disk", self.name()),
+            )));
+        };
+        if self.dirty {
+            return Ok(Some(Message::notify(
+                Level::Warn,
+                format!("save {} before following it", self.name()),
+            )));
+        }
+
+        log::info!("following {}", path.display());
+        self.follower = Some(Follower::spawn(path, stamp.len, self.format.encoding));
+        self.jump_bottom()?;
+
+        Ok(None)
     }
 
-    fn insert_above(&mut self, line: Line) {
-        self.above.push('\n');
-        self.above.push_str(line.as_ref());
-        self.recycle.push(line);
+    fn apply(&mut self, event: Event) -> Res {
+        match event {
+            Event::Reset(text) => {
+                let (lines, format) = text::decode_as(text.as_bytes(), UTF_8, false)?;
+                self.format.final_newline = format.final_newline;
+                self.reset(lines)?;
+                self.jump_bottom()
+            }
+
+            Event::Append(text) => {
+                let at_end = self.below.is_empty() && self.at_bottom();
+                let ends_with_newline = text.ends_with('\n');
+                let mut segments: Vec<_> = text
+                    .split('\n')
+                    .map(|segment| segment.strip_suffix('\r').unwrap_or(segment))
+                    .collect();
+                if ends_with_newline {
+                    segments.pop();
+                }
+
+                let mut segments = segments.into_iter();
+                if !self.format.final_newline {
+                    if let Some(first) = segments.next() {
+                        self.extend_last_line(first)?;
+                    }
+                }
+                self.push_lines(segments);
+                self.format.final_newline = ends_with_newline;
+
+                if at_end {
+                    self.jump_bottom()?;
+                }
+
+                Ok(())
+            }
+
+            Event::Stopped(_) => Ok(()),
+        }
     }
 
-    fn take_from_below(&mut self) -> Res<Option<Line>> {
+    fn extend_last_line(&mut self, text: &str) -> Res {
         if self.below.is_empty() {
-            Ok(None)
+            self.lines
+                .back_mut()
+                .context("at least one line")?
+                .append(text);
         } else {
-            let mut new_line = self.recycle.pop().unwrap_or_default();
-            let pos = self.below.rfind('\n').context("newline before each line")?;
+            let end = self.below[1..]
+                .find('\n')
+                .map_or(self.below.len(), |end| end + 1);
+            self.below.insert_str(end, text);
+        }
 
-            new_line.clear();
-            new_line.append(self.below[pos..].trim_start_matches('\n'));
-            self.below.truncate(pos);
+        Ok(())
+    }
 
-            Ok(Some(new_line))
+    fn push_lines<'text>(&mut self, lines: impl Iterator<Item = &'text str>) {
+        let height = self.bounds.height().into();
+        let mut reversed = vec![];
+
+        for line in lines {
+            if self.below.is_empty() && reversed.is_empty() && self.lines.len() < height {
+                self.lines.push_back(line.to_string().into());
+            } else {
+                reversed.push(line);
+            }
         }
+
+        let prefix: String = reversed
+            .into_iter()
+            .rev()
+            .flat_map(|line| ["\n", line])
+            .collect();
+        self.below.insert_str(0, &prefix);
     }
 
-    fn take_from_above(&mut self) -> Res<Option<Line>> {
-        if self.above.is_empty() {
-            Ok(None)
+    fn read_only_notice(&self) -> Option<Message> {
+        Some(Message::notify(
+            Level::Warn,
+            format!("{} is read-only", self.name()),
+        ))
+    }
+
+    fn find_next(&mut self) -> Res<Option<Message>> {
+        let Some(query) = self.last_query.clone().filter(|query| !query.is_empty()) else {
+            return Ok(None);
+        };
+
+        let current = self.offset + self.active;
+        let found = self
+            .all_lines()
+            .enumerate()
+            .skip(current + 1)
+            .chain(self.all_lines().enumerate().take(current + 1))
+            .find_map(|(i, line)| {
+                line.find(&query)
+                    .map(|byte| (i, line[..byte].chars().count()))
+            });
+
+        match found {
+            Some((line, column)) => {
+                self.goto(line + 1, column + 1)?;
+
+                Ok(None)
+            }
+            None => Ok(Some(Message::notify(
+                Level::Warn,
+                format!("'{query}' not found"),
+            ))),
+        }
+    }
+
+    fn page(&mut self, message: &Message) -> Res<Option<Option<Message>>> {
+        let page = self.lines.len().saturating_sub(1);
+
+        match message {
+            Message::Command(Command::PageDown) => {
+                for _ in 0..page {
+                    self.cursor_down()?;
+                }
+            }
+            Message::Command(Command::PageUp) => {
+                for _ in 0..page {
+                    self.cursor_up()?;
+                }
+            }
+            Message::Command(Command::Top) => self.jump_top()?,
+            Message::Command(Command::Bottom) => self.jump_bottom()?,
+            Message::Input(Input::KeyCombo(KeyCombo {
+                key: Key::Char(_),
+                ctrl: false,
+                alt: false,
+                ..
+            })) => {}
+            _ => return Ok(None),
+        }
+
+        Ok(Some(None))
+    }
+
+    pub fn context(&self) -> keymap::Context {
+        if self.dialog.is_some() {
+            keymap::Context::Prompt
+        } else if self.pager {
+            keymap::Context::Pager
         } else {
-            let mut new_line = self.recycle.pop().unwrap_or_default();
-            let pos = self.above.rfind('\n').context("newline before each line")?;
+            match &self.modal {
+                Some(Modal {
+                    mode: Mode::Insert, ..
+                }) => keymap::Context::Insert,
+                Some(_) => keymap::Context::Normal,
+                None => keymap::Context::Editor,
+            }
+        }
+    }
+
+    pub fn goto(&mut self, line: usize, column: usize) -> Res {
+        line::with_tab_size(self.settings.tab_size, || self.jump_to(line, column))
+    }
+
+    fn jump_to(&mut self, line: usize, column: usize) -> Res {
+        self.jump_top()?;
+        for _ in 1..line {
+            if !self.cursor_down()? {
+                break;
+            }
+        }
+
+        let mut index = Default::default();
+        for _ in 1..column {
+            match self.current_line()?.index_forward(index)? {
+                Some(next) => index = next,
+                None => break,
+            }
+        }
+        self.index = index.into();
+
+        Ok(())
+    }
+
+    pub fn is_stdin(&self) -> bool {
+        matches!(self.source, Source::Stdin)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn discard(&mut self) -> Res {
+        self.dirty = false;
+        self.journal.stale = false;
+        if let Some(swap) = self.journal.swap.take() {
+            swap::remove(&swap)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn name(&self) -> String {
+        match &self.source {
+            Source::File(path) => path.display().to_string(),
+            Source::Stdin => "stdin".into(),
+        }
+    }
+
+    pub fn save(&mut self) -> Res<bool> {
+        if let Some((text, format, stamp)) = self.disk_change()? {
+            let dialog = Dialog::new(
+                format!(
+                    "{} was changed by another program since it was opened",
+                    self.name()
+                ),
+                &[('o', "overwrite"), ('r', "reload")],
+            )
+            .with_diff(&self.text(), &text);
+            self.dialog = Some((
+                dialog,
+                Pending::Overwrite {
+                    text,
+                    format,
+                    stamp,
+                },
+            ));
+
+            Ok(false)
+        } else {
+            self.write_file()?;
+
+            Ok(true)
+        }
+    }
+
+    fn save_as(&mut self, path: PathBuf) -> Res<Option<Message>> {
+        let previous = (
+            mem::replace(&mut self.source, Source::File(path)),
+            self.stamp.take(),
+        );
+
+        match self.write_file() {
+            Ok(()) => {
+                self.readonly = self.pager;
+                self.configure();
+
+                Ok(Some(Message::notify(
+                    Level::Info,
+                    format!("saved {}", self.name()),
+                )))
+            }
+            Err(error) => {
+                let name = self.name();
+                (self.source, self.stamp) = previous;
+
+                match error.downcast_ref::<io::Error>() {
+                    Some(io_error) => Ok(Some(Message::notify(
+                        Level::Error,
+                        format!("cannot save {name}: {io_error}"),
+                    ))),
+                    None => Err(error),
+                }
+            }
+        }
+    }
+
+    fn trim_trailing_whitespace(&mut self) {
+        let trim = |region: &str| {
+            region
+                .split('\n')
+                .map(|line| line.trim_end_matches([' ', '\t']))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        self.above = trim(&self.above);
+        self.below = trim(&self.below);
+        for line in &mut self.lines {
+            line.trim_end();
+        }
+        self.forget_points();
+    }
+
+    fn write_file(&mut self) -> Res {
+        if self.editorconfig.trim_trailing_whitespace == Some(true) {
+            self.trim_trailing_whitespace();
+        }
+
+        if let Source::File(path) = &self.source {
+            let mut bytes = vec![];
+            self.write_to(&mut bytes)?;
+            fs::write(path, &bytes)?;
+            self.stamp = Some(Stamp::new(&bytes, &fs::metadata(path)?));
+            self.dirty = false;
+
+            self.journal.stale = false;
+            if let Some(swap) = self.journal.swap.take() {
+                swap::remove(&swap)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write_to(&self, writer: impl io::Write) -> Res {
+        let mut writer = BufWriter::new(writer);
+        text::encode(&mut writer, self.all_lines(), self.format)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn text(&self) -> String {
+        text::join(self.all_lines(), self.format)
+    }
+
+    fn all_lines(&self) -> impl Iterator<Item = &str> {
+        let above = self
+            .above
+            .strip_prefix('\n')
+            .into_iter()
+            .flat_map(|above| above.split('\n'));
+        let below = self
+            .below
+            .strip_prefix('\n')
+            .into_iter()
+            .flat_map(|below| below.rsplit('\n'));
+
+        above
+            .chain(self.lines.iter().map(AsRef::as_ref))
+            .chain(below)
+    }
+
+    fn mutates(message: &Message) -> bool {
+        matches!(
+            message,
+            Message::Input(Input::KeyCombo(KeyCombo {
+                key: Key::Char(_),
+                ctrl: false,
+                alt: false,
+                ..
+            })) | Message::Command(
+                Command::Newline
+                    | Command::OpenAbove
+                    | Command::OpenBelow
+                    | Command::Tab
+                    | Command::Backspace
+                    | Command::BackspaceWord
+                    | Command::Delete
+                    | Command::DeleteWord
+                    | Command::KillLine
+                    | Command::Yank
+            )
+        )
+    }
+
+    fn line_count(&self) -> usize {
+        let count = |region: &str| region.bytes().filter(|&byte| byte == b'\n').count();
+
+        count(&self.above) + self.lines.len() + count(&self.below)
+    }
+
+    fn current_line(&self) -> Res<&Line> {
+        self.lines.get(self.active).context("active is valid")
+    }
+
+    fn current_line_mut(&mut self) -> Res<&mut Line> {
+        self.lines.get_mut(self.active).context("active is valid")
+    }
+
+    fn at_top(&self) -> bool {
+        self.active == 0
+    }
+
+    fn at_bottom(&self) -> bool {
+        self.active == self.lines.len() - 1
+    }
+
+    fn insert_below(&mut self, line: Line) {
+        self.below.push('\n');
+        self.below.push_str(line.as_ref());
+        self.recycle.push(line);
+    }
+
+    fn insert_above(&mut self, line: Line) {
+        self.above.push('\n');
+        self.above.push_str(line.as_ref());
+        self.recycle.push(line);
+    }
+
+    fn take_from_below(&mut self) -> Res<Option<Line>> {
+        if self.below.is_empty() {
+            Ok(None)
+        } else {
+            let mut new_line = self.recycle.pop().unwrap_or_default();
+            let pos = self.below.rfind('\n').context("newline before each line")?;
+
+            new_line.clear();
+            new_line.append(self.below[pos..].trim_start_matches('\n'));
+            self.below.truncate(pos);
+
+            Ok(Some(new_line))
+        }
+    }
+
+    fn take_from_above(&mut self) -> Res<Option<Line>> {
+        if self.above.is_empty() {
+            Ok(None)
+        } else {
+            let mut new_line = self.recycle.pop().unwrap_or_default();
+            let pos = self.above.rfind('\n').context("newline before each line")?;
+
+            new_line.clear();
+            new_line.append(self.above[pos..].trim_start_matches('\n'));
+            self.above.truncate(pos);
+
+            Ok(Some(new_line))
+        }
+    }
+
+    fn scroll_down(&mut self) -> Res<bool> {
+        if let Some(line_from_below) = self.take_from_below()? {
+            self.lines.push_back(line_from_below);
+            let line_to_above = self.lines.pop_front().context("at least one line")?;
+            self.insert_above(line_to_above);
+            self.offset += 1;
+
+            let new_line_num_width = match self.offset {
+                1_000 => 4,
+                10_000 => 5,
+                100_000 => 6,
+                1_000_000 => 7,
+                10_000_000 => 8,
+                _ => 0,
+            };
+            self.line_num_width = self.line_num_width.max(new_line_num_width);
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn scroll_up(&mut self) -> Res<bool> {
+        if let Some(line_from_above) = self.take_from_above()? {
+            self.lines.push_front(line_from_above);
+            self.fix_lines()?;
+            self.offset -= 1;
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // The configured grace is validated, but a small enough tile can still be shorter than it.
+    fn scroll_grace(&self) -> usize {
+        self.settings
+            .scroll_grace
+            .min(self.lines.len().saturating_sub(1) / 2)
+            .max(1)
+    }
+
+    fn cursor_down(&mut self) -> Res<bool> {
+        if self.active < self.lines.len().saturating_sub(self.scroll_grace()) {
+            self.active += 1;
+            self.index.invalidate();
+
+            Ok(true)
+        } else if self.scroll_down()? {
+            self.index.invalidate();
+
+            Ok(true)
+        } else if self.active < self.lines.len() - 1 {
+            self.active += 1;
+            self.index.invalidate();
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn cursor_up(&mut self) -> Res<bool> {
+        if self.active > self.scroll_grace() {
+            self.active -= 1;
+            self.index.invalidate();
+
+            Ok(true)
+        } else if self.scroll_up()? {
+            self.index.invalidate();
+
+            Ok(true)
+        } else if self.active > 0 {
+            self.active -= 1;
+            self.index.invalidate();
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn jump_top(&mut self) -> Res {
+        while self.cursor_up()? {}
+
+        Ok(())
+    }
+
+    fn jump_bottom(&mut self) -> Res {
+        while self.cursor_down()? {}
+
+        Ok(())
+    }
+
+    fn fix_lines(&mut self) -> Res {
+        let (len, height) = (self.lines.len(), self.bounds.height().into());
+
+        match len.cmp(&height) {
+            Ordering::Greater => {
+                for _ in height..len {
+                    let new_line = self.lines.pop_back().context("len > height >= 0")?;
+                    self.insert_below(new_line);
+                }
+            }
+
+            Ordering::Less => {
+                for _ in len..height {
+                    if let Some(line_from_below) = self.take_from_below()? {
+                        self.lines.push_back(line_from_below);
+                    }
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn type_char(&mut self, c: char) -> Res {
+        let corrected = self.current_line()?.correct_index(self.index);
+
+        self.current_line_mut()?.insert(corrected, c);
+        self.index = self
+            .current_line()?
+            .index_forward(corrected)?
+            .unwrap_or(corrected)
+            .into();
+
+        Ok(())
+    }
+
+    fn modal(&mut self) -> Res<&mut Modal> {
+        self.modal.as_mut().context("modal editing is on")
+    }
+
+    fn position(&self) -> Res<Point> {
+        Ok((
+            self.offset + self.active,
+            self.current_line()?.correct_index(self.index),
+        ))
+    }
+
+    fn move_to(&mut self, (line, index): Point) -> Res {
+        let current = self.offset + self.active;
+
+        for _ in line..current {
+            self.cursor_up()?;
+        }
+        for _ in current..line {
+            self.cursor_down()?;
+        }
+        self.index = index.into();
+
+        Ok(())
+    }
+
+    fn line_end(&mut self, line: usize) -> Res<Point> {
+        self.move_to((line, Index::default()))?;
+
+        Ok((line, self.current_line()?.index_back(self.index)?))
+    }
+
+    fn step_back(&mut self) -> Res {
+        let (_, index) = self.position()?;
+        self.index = self
+            .current_line()?
+            .index_backward(index)?
+            .unwrap_or(index)
+            .into();
+
+        Ok(())
+    }
+
+    fn clamp(&mut self) -> Res {
+        if let RawIndex::Valid(index) = self.index {
+            if self.current_line()?.at_back(index) {
+                self.step_back()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn selection(&self) -> Res<Option<(Point, Point)>> {
+        let anchor = match (&self.modal, self.mark) {
+            (
+                Some(Modal {
+                    mode: Mode::Visual,
+                    anchor,
+                    ..
+                }),
+                _,
+            ) => *anchor,
+            (_, Some(mark)) => mark,
+            _ => return Ok(None),
+        };
+        let cursor = self.position()?;
+        let key = |(line, index): Point| (line, index.byte());
+
+        Ok(Some(if key(anchor) <= key(cursor) {
+            (anchor, cursor)
+        } else {
+            (cursor, anchor)
+        }))
+    }
+
+    fn range_text(&self, from: Point, to: Point) -> String {
+        self.all_lines()
+            .enumerate()
+            .skip(from.0)
+            .take(to.0 + 1 - from.0)
+            .map(|(i, line)| {
+                let start = if i == from.0 { from.1.byte() } else { 0 };
+                let end = if i == to.0 { to.1.byte() } else { line.len() };
+
+                &line[start..end]
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn delete_lines(&mut self, count: usize) -> Res {
+        for _ in 0..count {
+            if self.line_count() == 1 {
+                self.current_line_mut()?.clear();
+                break;
+            }
+
+            let line = self.lines.remove(self.active).context("active is valid")?;
+            self.recycle.push(line);
+            self.fix_lines()?;
+
+            if self.active == self.lines.len() {
+                self.active -= 1;
+                if self.scroll_up()? {
+                    self.active += 1;
+                }
+                break;
+            }
+        }
+        self.index = RawIndex::index_front();
+
+        Ok(())
+    }
+
+    fn delete_range(&mut self, from: Point, to: Point) -> Res {
+        let text = self.range_text(from, to);
+        let (first, last) = match text.split_once('\n') {
+            Some((first, rest)) => (first, rest.rsplit('\n').next().unwrap_or_default()),
+            None => (text.as_str(), ""),
+        };
+        let removed = first.chars().count() + last.chars().count();
+
+        if to.0 > from.0 + 1 {
+            self.move_to((from.0 + 1, Index::default()))?;
+            self.delete_lines(to.0 - from.0 - 1)?;
+        }
+        self.move_to(from)?;
+        if to.0 > from.0 {
+            self.join_below()?;
+        }
+
+        let line = self.current_line()?;
+        let mut end = from.1;
+        for _ in 0..removed {
+            end = line.index_forward(end)?.unwrap_or(end);
+        }
+        self.current_line_mut()?.remove_range(from.1, end);
+        self.index = from.1.into();
+
+        Ok(())
+    }
+
+    fn insert_text(&mut self, text: &str) -> Res {
+        for c in text.chars() {
+            match c {
+                '\n' => self.newline()?,
+                c => self.type_char(c)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn kill_region(&mut self, kill: bool, append: bool) -> Res<Option<Message>> {
+        if kill && self.is_readonly() {
+            return Ok(self.read_only_notice());
+        }
+        let Some((from, to)) = self.selection()? else {
+            return Ok(Some(Message::notify(Level::Warn, "the mark is not set")));
+        };
+
+        let text = self.range_text(from, to);
+        shared::set(|shared| shared.kill_ring.kill(&text, append));
+        self.mark = None;
+
+        if kill {
+            self.delete_range(from, to)?;
+            self.chain = Some(Chain::Kill);
+            self.touch();
+        }
+
+        Ok(None)
+    }
+
+    fn touch(&mut self) {
+        self.dirty = true;
+        self.journal.stale = true;
+    }
+
+    fn word_motion(&mut self, motion: Motion) -> Res {
+        let line = self.current_line()?;
+        let index = line.correct_index(self.index);
+        let next = match motion {
+            Motion::Left => line.index_backward(index)?,
+            Motion::Right => line.index_forward(index)?,
+            Motion::WordStart => line.index_next_word(index)?,
+            Motion::WordEnd => line.index_word_end(index)?,
+            _ => line.index_prev_word(index)?,
+        };
+
+        self.index = match (next, motion) {
+            (Some(next), _) => next,
+            (None, Motion::Left | Motion::Right) => index,
+            (None, Motion::WordBack) if self.cursor_up()? => {
+                let line = self.current_line()?;
+                let back = line.index_back(RawIndex::index_front())?;
+
+                line.index_prev_word(back)?.unwrap_or_default()
+            }
+            (None, Motion::WordStart | Motion::WordEnd) if self.cursor_down()? => {
+                let line = self.current_line()?;
+
+                match (line.index_first_word()?, motion) {
+                    (Some(first), Motion::WordEnd) => {
+                        let after = line.index_forward_word(first)?.unwrap_or(first);
+                        line.index_backward(after)?.unwrap_or(first)
+                    }
+                    (first, _) => first.unwrap_or_default(),
+                }
+            }
+            (None, Motion::WordBack) => Index::default(),
+            (None, _) => self.current_line()?.index_back(index.into())?,
+        }
+        .into();
+
+        Ok(())
+    }
+
+    fn vi_motion(&mut self, motion: Motion, count: Option<usize>) -> Res {
+        let times = count.unwrap_or(1);
+        let lines = times.min(self.line_count());
+
+        match motion {
+            Motion::Top | Motion::Bottom => {
+                match (count, motion) {
+                    (Some(line), _) => self.goto(line, 1)?,
+                    (None, Motion::Top) => self.jump_top()?,
+                    (None, _) => self.jump_bottom()?,
+                }
+                self.index = RawIndex::index_front();
+            }
+            Motion::Up => {
+                for _ in 0..lines {
+                    self.cursor_up()?;
+                }
+            }
+            Motion::Down => {
+                for _ in 0..lines {
+                    self.cursor_down()?;
+                }
+            }
+            Motion::LineStart => self.index = RawIndex::index_front(),
+            Motion::LineEnd => {
+                for _ in 1..lines {
+                    self.cursor_down()?;
+                }
+                self.index = self.current_line()?.index_back(self.index)?.into();
+            }
+            Motion::WholeLine | Motion::Selection => {}
+            Motion::Left
+            | Motion::Right
+            | Motion::WordStart
+            | Motion::WordEnd
+            | Motion::WordBack => {
+                for _ in 0..times {
+                    self.word_motion(motion)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn vi_operate(
+        &mut self,
+        operator: Operator,
+        motion: Motion,
+        count: Option<usize>,
+    ) -> Res<Option<Message>> {
+        if operator != Operator::Yank && self.is_readonly() {
+            return Ok(self.read_only_notice());
+        }
+
+        let start = self.position()?;
+        let on_word = self.current_line()?.as_ref()[start.1.byte()..]
+            .starts_with(|c: char| !c.is_whitespace());
+        let change_word = operator == Operator::Change && motion == Motion::WordStart && on_word;
+        let motion = match change_word {
+            true => Motion::WordEnd,
+            false => motion,
+        };
+
+        let (from, to) = match motion {
+            Motion::Selection => {
+                let (from, to) = self.selection()?.context("visual mode")?;
+                self.move_to(to)?;
+                let after = self.current_line()?.index_forward(to.1)?.unwrap_or(to.1);
+
+                (from, (to.0, after))
+            }
+            Motion::WholeLine => {
+                let last = start.0.saturating_add(count.unwrap_or(1)) - 1;
+
+                (start, (last.min(self.line_count() - 1), start.1))
+            }
+            _ => {
+                if change_word {
+                    // Like `ce`, except that the word under the cursor counts even when the
+                    // cursor is already on its last character.
+                    let line = self.current_line()?;
+                    let after = line.index_forward_word(start.1)?.unwrap_or(start.1);
+                    self.index = line.index_backward(after)?.unwrap_or(start.1).into();
+                    self.vi_motion(motion, Some(count.unwrap_or(1) - 1))?;
+                } else {
+                    self.vi_motion(motion, count)?;
+                }
+                let mut end = self.position()?;
+
+                if motion == Motion::WordStart && end.0 > start.0 {
+                    end = self.line_end(start.0)?;
+                } else if motion == Motion::WordEnd {
+                    end.1 = self.current_line()?.index_forward(end.1)?.unwrap_or(end.1);
+                }
+
+                if (end.0, end.1.byte()) < (start.0, start.1.byte()) {
+                    (end, start)
+                } else {
+                    (start, end)
+                }
+            }
+        };
+
+        let linewise = motion.is_linewise();
+        let text = match linewise {
+            true => {
+                let end = self.line_end(to.0)?;
+                self.range_text((from.0, Index::default()), end)
+            }
+            false => self.range_text(from, to),
+        };
+        shared::set(|shared| shared.register = Register { text, linewise });
+
+        match (operator, linewise) {
+            (Operator::Yank, true) => self.move_to((from.0, start.1))?,
+            (Operator::Yank, false) => self.move_to(from)?,
+            (Operator::Delete, true) => {
+                self.move_to((from.0, Index::default()))?;
+                self.delete_lines(to.0 + 1 - from.0)?;
+            }
+            (Operator::Change, true) => {
+                let end = self.line_end(to.0)?;
+                self.delete_range((from.0, Index::default()), end)?;
+            }
+            (Operator::Delete | Operator::Change, false) => self.delete_range(from, to)?,
+        }
+
+        if operator != Operator::Yank {
+            self.touch();
+        }
+        self.modal()?.mode = match operator {
+            Operator::Change => Mode::Insert,
+            Operator::Delete | Operator::Yank => Mode::Normal,
+        };
+
+        Ok(None)
+    }
+
+    fn vi_insert(&mut self, insert: Insert) -> Res<Option<Message>> {
+        if self.is_readonly() {
+            return Ok(self.read_only_notice());
+        }
+
+        let line = self.current_line()?;
+        let index = line.correct_index(self.index);
+
+        match insert {
+            Insert::Before => {}
+            Insert::After => self.index = line.index_forward(index)?.unwrap_or(index).into(),
+            Insert::LineStart => self.index = RawIndex::index_front(),
+            Insert::LineEnd => self.index = line.index_back(index.into())?.into(),
+            Insert::Above => {
+                self.index = RawIndex::index_front();
+                self.newline()?;
+                self.cursor_up()?;
+                self.touch();
+            }
+            Insert::Below => {
+                self.index = line.index_back(index.into())?.into();
+                self.newline()?;
+                self.touch();
+            }
+        }
+        self.modal()?.mode = Mode::Insert;
+
+        Ok(None)
+    }
+
+    fn vi_paste(&mut self, after: bool, count: Option<usize>) -> Res<Option<Message>> {
+        if self.is_readonly() {
+            return Ok(self.read_only_notice());
+        }
+
+        let Register { text, linewise } = shared::get(|shared| shared.register.clone());
+        let times = count.unwrap_or(1).min(MAX_PASTE / text.len().max(1)).max(1);
+        let text = match linewise {
+            true => vec![text; times].join("\n"),
+            false => text.repeat(times),
+        };
+        let (line, index) = self.position()?;
+
+        match (linewise, after) {
+            (true, true) => {
+                self.index = self.current_line()?.index_back(index.into())?.into();
+                self.newline()?;
+                self.insert_text(&text)?;
+                self.move_to((line + 1, Index::default()))?;
+            }
+            (true, false) => {
+                self.index = RawIndex::index_front();
+                self.insert_text(&text)?;
+                self.newline()?;
+                self.move_to((line, Index::default()))?;
+            }
+            (false, _) => {
+                if after {
+                    self.index = self
+                        .current_line()?
+                        .index_forward(index)?
+                        .unwrap_or(index)
+                        .into();
+                }
+                self.insert_text(&text)?;
+                self.step_back()?;
+            }
+        }
+        self.touch();
+
+        Ok(None)
+    }
+
+    fn act(&mut self, action: Action) -> Res<Option<Message>> {
+        match action {
+            Action::Move { count, motion } => {
+                self.vi_motion(motion, count)?;
+
+                Ok(None)
+            }
+            Action::Operate {
+                count,
+                operator,
+                motion,
+            } => self.vi_operate(operator, motion, count),
+            Action::Insert(insert) => self.vi_insert(insert),
+            Action::Visual => {
+                let position = self.position()?;
+                let modal = self.modal()?;
+
+                modal.anchor = position;
+                modal.mode = match modal.mode {
+                    Mode::Visual => Mode::Normal,
+                    _ => Mode::Visual,
+                };
+
+                Ok(None)
+            }
+            Action::Paste { count, after } => self.vi_paste(after, count),
+            Action::Repeat { count } => {
+                let modal = self.modal()?;
+                let keys = modal.last_change.clone();
+                modal.change.clear();
+
+                let mut update = None;
+                let snapshot = |portal: &Self| {
+                    let line = portal
+                        .current_line()
+                        .ok()
+                        .map(|line| String::from(line.as_ref()));
+
+                    let position = portal
+                        .position()
+                        .ok()
+                        .map(|(line, index)| (line, index.byte()));
+
+                    (portal.line_count(), position, line)
+                };
+                for _ in 0..count.unwrap_or(1) {
+                    let before = snapshot(self);
+                    for message in &keys {
+                        update = self.update(message)?.or(update);
+                    }
+                    // Stop once the change no longer does anything, e.g. `x` on an empty line.
+                    if snapshot(self) == before {
+                        break;
+                    }
+                }
+
+                Ok(update)
+            }
+        }
+    }
+
+    fn vi_update(&mut self, message: &Message) -> Res<Option<Option<Message>>> {
+        let Some(modal) = &mut self.modal else {
+            return Ok(None);
+        };
+        let normal = matches!(message, Message::Command(Command::NormalMode));
+
+        if modal.mode == Mode::Insert {
+            if !modal.change.is_empty() && (normal || Self::mutates(message)) {
+                modal.change.push(message.clone());
+            }
+            if !normal {
+                return Ok(None);
+            }
 
-            new_line.clear();
-            new_line.append(self.above[pos..].trim_start_matches('\n'));
-            self.above.truncate(pos);
+            modal.mode = Mode::Normal;
+            if !modal.change.is_empty() {
+                modal.last_change = mem::take(&mut modal.change);
+            }
+            self.step_back()?;
 
-            Ok(Some(new_line))
+            return Ok(Some(None));
         }
-    }
 
-    fn scroll_down(&mut self) -> Res<bool> {
-        if let Some(line_from_below) = self.take_from_below()? {
-            self.lines.push_back(line_from_below);
-            let line_to_above = self.lines.pop_front().context("at least one line")?;
-            self.insert_above(line_to_above);
-            self.offset += 1;
+        let c = match message {
+            Message::Command(Command::NormalMode) => {
+                modal.parser.reset();
+                modal.change.clear();
+                modal.mode = Mode::Normal;
 
-            let new_line_num_width = match self.offset {
-                1_000 => 4,
-                10_000 => 5,
-                100_000 => 6,
-                1_000_000 => 7,
-                10_000_000 => 8,
-                _ => 0,
-            };
-            self.line_num_width = self.line_num_width.max(new_line_num_width);
+                return Ok(Some(None));
+            }
+            Message::Command(
+                Command::Newline | Command::Tab | Command::Backspace | Command::BackspaceWord,
+            ) => return Ok(Some(None)),
+            &Message::Input(Input::KeyCombo(KeyCombo {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+                ..
+            })) => c,
+            _ => return Ok(None),
+        };
+
+        modal.change.push(message.clone());
+        let Some(action) = modal.parser.feed(modal.mode, c) else {
+            if modal.parser.pending().is_empty() {
+                modal.change.clear();
+            }
 
-            Ok(true)
-        } else {
-            Ok(false)
+            return Ok(Some(None));
+        };
+        let visual = modal.mode == Mode::Visual;
+        let update = self.act(action)?;
+
+        let modal = self.modal()?;
+        if !action.is_change() || visual {
+            modal.change.clear();
+        } else if modal.mode != Mode::Insert {
+            modal.last_change = mem::take(&mut modal.change);
+        }
+        if modal.mode != Mode::Insert {
+            self.clamp()?;
         }
+
+        Ok(Some(update))
     }
 
-    fn scroll_up(&mut self) -> Res<bool> {
-        if let Some(line_from_above) = self.take_from_above()? {
-            self.lines.push_front(line_from_above);
-            self.fix_lines()?;
-            self.offset -= 1;
+    fn newline(&mut self) -> Res {
+        let corrected = self.current_line()?.correct_index(self.index);
+        let new_line = self.current_line_mut()?.split_at(corrected)?;
 
-            Ok(true)
+        self.index = RawIndex::index_front();
+        if self.cursor_down()? {
+            self.lines.insert(self.active, new_line);
+            self.cursor_up()?;
         } else {
-            Ok(false)
+            self.lines.push_back(new_line);
         }
+        self.fix_lines()?;
+        self.cursor_down()?;
+
+        Ok(())
     }
 
-    fn cursor_down(&mut self) -> Res<bool> {
-        if self.active < self.lines.len() - SCROLL_GRACE {
-            self.active += 1;
-            self.index.invalidate();
+    fn join_below(&mut self) -> Res {
+        if !self.at_bottom() {
+            let line = self.lines.remove(self.active).context("active is valid")?;
 
-            Ok(true)
-        } else if self.scroll_down()? {
-            self.index.invalidate();
+            self.fix_lines()?;
+            self.current_line_mut()?.prepend(line);
+        }
 
-            Ok(true)
-        } else if self.active < self.lines.len() - 1 {
-            self.active += 1;
-            self.index.invalidate();
+        Ok(())
+    }
 
-            Ok(true)
-        } else {
-            Ok(false)
+    pub fn update(&mut self, message: &Message) -> Res<Option<Message>> {
+        if let Message::Command(Command::ReloadConfig) = message {
+            self.configure();
+
+            return Ok(None);
         }
+
+        line::with_tab_size(self.settings.tab_size, || self.handle(message))
     }
 
-    fn cursor_up(&mut self) -> Res<bool> {
-        if self.active > SCROLL_GRACE {
-            self.active -= 1;
-            self.index.invalidate();
+    fn handle(&mut self, message: &Message) -> Res<Option<Message>> {
+        if let Message::Input(Input::FocusGained) = message {
+            self.check_disk()?;
 
-            Ok(true)
-        } else if self.scroll_up()? {
-            self.index.invalidate();
+            return Ok(None);
+        }
 
-            Ok(true)
-        } else if self.active > 0 {
-            self.active -= 1;
-            self.index.invalidate();
+        if let Some((dialog, _)) = &mut self.dialog {
+            if let Some(choice) = dialog.update(message) {
+                let (_, pending) = self.dialog.take().context("dialog is open")?;
+                self.resolve(pending, choice)?;
+            }
 
-            Ok(true)
-        } else {
-            Ok(false)
+            return Ok(None);
         }
-    }
 
-    fn jump_top(&mut self) -> Res {
-        while self.cursor_up()? {}
+        if self.pager {
+            if let Some(update) = self.page(message)? {
+                return Ok(update);
+            }
+        }
 
-        Ok(())
-    }
+        let chain = self.chain.take();
+        if let Some(update) = self.vi_update(message)? {
+            return Ok(update);
+        }
 
-    fn jump_bottom(&mut self) -> Res {
-        while self.cursor_down()? {}
+        if Self::mutates(message) {
+            if self.is_readonly() {
+                return Ok(self.read_only_notice());
+            }
+            self.mark = None;
+            // Killing and yanking can turn out to be no-ops, so they touch the buffer themselves.
+            if !matches!(message, Message::Command(Command::KillLine | Command::Yank)) {
+                self.touch();
+            }
+        }
 
-        Ok(())
-    }
+        match message {
+            Message::Command(Command::Save) => {
+                if self.is_readonly() {
+                    return Ok(self.read_only_notice());
+                }
 
-    fn fix_lines(&mut self) -> Res {
-        let (len, height) = (self.lines.len(), self.bounds.height().into());
+                match self.save() {
+                    Err(error) => match error.downcast_ref::<io::Error>() {
+                        Some(io_error) => Ok(Some(Message::notify(
+                            Level::Error,
+                            format!("cannot save {}: {io_error}", self.name()),
+                        ))),
+                        None => Err(error),
+                    },
+                    Ok(false) => Ok(None),
+                    Ok(true) => Ok(Some(Message::notify(
+                        Level::Info,
+                        format!("saved {}", self.name()),
+                    ))),
+                }
+            }
 
-        match len.cmp(&height) {
-            Ordering::Greater => {
-                for _ in height..len {
-                    let new_line = self.lines.pop_back().context("len > height >= 0")?;
-                    self.insert_below(new_line);
+            Message::Command(Command::CycleEnding) => {
+                if self.is_readonly() {
+                    return Ok(self.read_only_notice());
                 }
+
+                self.format.ending = self.format.ending.next();
+                self.dirty = true;
+                self.journal.stale = true;
+
+                Ok(None)
             }
 
-            Ordering::Less => {
-                for _ in len..height {
-                    if let Some(line_from_below) = self.take_from_below()? {
-                        self.lines.push_back(line_from_below);
-                    }
+            Message::Command(Command::SaveAs) => {
+                if self.follower.is_some() {
+                    return Ok(self.read_only_notice());
                 }
+
+                let text = match &self.source {
+                    Source::File(path) => path.display().to_string(),
+                    Source::Stdin => String::new(),
+                };
+
+                Ok(Some(Message::Prompt {
+                    kind: PromptKind::SaveAs,
+                    text,
+                    complete: Some(complete::paths),
+                }))
             }
 
-            _ => {}
-        }
+            Message::PromptResult {
+                kind: PromptKind::SaveAs,
+                text,
+            } => match text.trim() {
+                "" => Ok(None),
+                path => self.save_as(path.into()),
+            },
 
-        Ok(())
-    }
+            Message::Command(Command::Goto) => Ok(Some(Message::Prompt {
+                kind: PromptKind::Goto,
+                text: String::new(),
+                complete: None,
+            })),
 
-    fn type_char(&mut self, c: char) -> Res {
-        let corrected = self.current_line()?.correct_index(self.index);
+            Message::PromptResult {
+                kind: PromptKind::Goto,
+                text,
+            } => match text.parse::<Position>() {
+                Ok(Position { line, column }) => {
+                    self.goto(line, column)?;
 
-        self.current_line_mut()?.insert(corrected, c);
-        self.index = self
-            .current_line()?
-            .index_forward(corrected)?
-            .unwrap_or(corrected)
-            .into();
+                    Ok(None)
+                }
+                Err(error) => Ok(Some(Message::notify(Level::Warn, error.to_string()))),
+            },
 
-        Ok(())
-    }
+            Message::Command(Command::SearchStart) => Ok(Some(Message::Prompt {
+                kind: PromptKind::Search,
+                text: self.last_query.clone().unwrap_or_default(),
+                complete: None,
+            })),
 
-    pub fn update(&mut self, message: &Message) -> Res<Option<Message>> {
-        match message {
-            pressed!(Key::Up) => {
+            Message::PromptResult {
+                kind: PromptKind::Search,
+                text,
+            } => {
+                self.last_query = Some(text.clone());
+
+                self.find_next()
+            }
+
+            Message::Command(Command::SearchNext) => self.find_next(),
+
+            Message::Command(Command::Follow) => self.set_follow(self.follower.is_none()),
+
+            Message::Command(Command::ReadOnly) => {
+                self.set_readonly(!self.readonly);
+
+                Ok(Some(Message::notify(
+                    Level::Info,
+                    format!(
+                        "{} is {}",
+                        self.name(),
+                        if self.readonly {
+                            "read-only"
+                        } else {
+                            "editable"
+                        }
+                    ),
+                )))
+            }
+
+            Message::Command(Command::CursorUp) => {
                 if !self.cursor_up()? {
                     self.index = RawIndex::index_front();
                 }
@@ -266,7 +1919,7 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Down) => {
+            Message::Command(Command::CursorDown) => {
                 if !self.cursor_down()? {
                     self.index = self.current_line()?.index_back(self.index)?.into();
                 }
@@ -274,7 +1927,7 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Left, ctrl) => {
+            Message::Command(Command::WordLeft) => {
                 let corrected = self.current_line()?.correct_index(self.index);
                 let index =
                     if let Some(index) = self.current_line()?.index_backward_word(corrected)? {
@@ -290,7 +1943,7 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Left) => {
+            Message::Command(Command::CursorLeft) => {
                 let corrected = self.current_line()?.correct_index(self.index);
 
                 self.index = if let Some(index) = self.current_line()?.index_backward(corrected)? {
@@ -305,7 +1958,7 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Right, ctrl) => {
+            Message::Command(Command::WordRight) => {
                 let corrected = self.current_line()?.correct_index(self.index);
 
                 self.index =
@@ -320,7 +1973,7 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Right) => {
+            Message::Command(Command::CursorRight) => {
                 let corrected = self.current_line()?.correct_index(self.index);
 
                 self.index = if let Some(index) = self.current_line()?.index_forward(corrected)? {
@@ -334,52 +1987,65 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Home, ctrl) => {
+            Message::Command(Command::Top) => {
                 self.jump_top()?;
                 self.index = RawIndex::index_front();
 
                 Ok(None)
             }
 
-            pressed!(Key::Home) => {
+            Message::Command(Command::LineStart) => {
                 self.index = RawIndex::index_front();
 
                 Ok(None)
             }
 
-            pressed!(Key::End, ctrl) => {
+            Message::Command(Command::Bottom) => {
                 self.jump_bottom()?;
                 self.index = self.current_line()?.index_back(self.index)?.into();
 
                 Ok(None)
             }
 
-            pressed!(Key::End) => {
+            Message::Command(Command::LineEnd) => {
                 self.index = self.current_line()?.index_back(self.index)?.into();
 
                 Ok(None)
             }
 
-            &pressed!(Key::Char(c)) => {
+            &Message::Input(Input::KeyCombo(KeyCombo {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+                ..
+            })) => {
                 self.type_char(c)?;
 
                 Ok(None)
             }
 
-            pressed!(Key::Tab) => {
-                self.type_char('\t')?;
+            Message::Command(Command::Tab) => {
+                match self.editorconfig.soft_tab() {
+                    Some(width) => {
+                        let column = self.current_line()?.correct_index(self.index).display();
+                        for _ in 0..width - column % width {
+                            self.type_char(' ')?;
+                        }
+                    }
+                    None => self.type_char('\t')?,
+                }
 
                 Ok(None)
             }
 
-            pressed!(Key::Enter, shift + ctrl) => {
+            Message::Command(Command::OpenAbove) => {
                 self.lines.insert(self.active, Default::default());
                 self.fix_lines()?;
 
                 Ok(None)
             }
 
-            pressed!(Key::Enter, ctrl) => {
+            Message::Command(Command::OpenBelow) => {
                 self.cursor_down()?;
                 self.lines.insert(self.active, Default::default());
                 self.fix_lines()?;
@@ -387,24 +2053,13 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Enter) => {
-                let corrected = self.current_line()?.correct_index(self.index);
-                let new_line = self.current_line_mut()?.split_at(corrected)?;
-
-                self.index = RawIndex::index_front();
-                if self.cursor_down()? {
-                    self.lines.insert(self.active, new_line);
-                    self.cursor_up()?;
-                } else {
-                    self.lines.push_back(new_line);
-                }
-                self.fix_lines()?;
-                self.cursor_down()?;
+            Message::Command(Command::Newline) => {
+                self.newline()?;
 
                 Ok(None)
             }
 
-            pressed!(Key::Backspace, ctrl) => {
+            Message::Command(Command::BackspaceWord) => {
                 if self.index.at_front() {
                     if !self.at_top() {
                         let line = self.lines.remove(self.active).context("active is valid")?;
@@ -428,7 +2083,7 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Backspace) => {
+            Message::Command(Command::Backspace) => {
                 if self.index.at_front() {
                     if !self.at_top() {
                         let line = self.lines.remove(self.active).context("active is valid")?;
@@ -452,16 +2107,11 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Delete, ctrl) => {
+            Message::Command(Command::DeleteWord) => {
                 let corrected = self.current_line()?.correct_index(self.index);
 
                 if self.current_line()?.at_back(corrected) {
-                    if !self.at_bottom() {
-                        let line = self.lines.remove(self.active).context("active is valid")?;
-
-                        self.fix_lines()?;
-                        self.current_line_mut()?.prepend(line);
-                    }
+                    self.join_below()?;
                 } else {
                     let index =
                         if let Some(index) = self.current_line()?.index_forward_word(corrected)? {
@@ -476,16 +2126,11 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::Delete) => {
+            Message::Command(Command::Delete) => {
                 let corrected = self.current_line()?.correct_index(self.index);
 
                 if self.current_line()?.at_back(corrected) {
-                    if !self.at_bottom() {
-                        let line = self.lines.remove(self.active).context("active is valid")?;
-
-                        self.fix_lines()?;
-                        self.current_line_mut()?.prepend(line);
-                    }
+                    self.join_below()?;
                 } else {
                     self.current_line_mut()?.remove(corrected);
                 }
@@ -494,7 +2139,78 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::PageDown) => {
+            Message::Command(Command::SetMark) => {
+                self.mark = Some(self.position()?);
+
+                Ok(None)
+            }
+
+            Message::Command(Command::ClearMark) => {
+                self.mark = None;
+
+                Ok(None)
+            }
+
+            Message::Command(Command::KillRegion) => {
+                self.kill_region(true, matches!(chain, Some(Chain::Kill)))
+            }
+
+            Message::Command(Command::CopyRegion) => self.kill_region(false, false),
+
+            Message::Command(Command::KillLine) => {
+                let from = self.position()?;
+                let line = self.current_line()?;
+                let to = if !line.at_back(from.1) {
+                    (from.0, line.index_back(from.1.into())?)
+                } else if from.0 + 1 < self.line_count() {
+                    (from.0 + 1, Index::default())
+                } else {
+                    return Ok(None);
+                };
+
+                let text = self.range_text(from, to);
+                let append = matches!(chain, Some(Chain::Kill));
+                shared::set(|shared| shared.kill_ring.kill(&text, append));
+                self.delete_range(from, to)?;
+                self.chain = Some(Chain::Kill);
+                self.touch();
+
+                Ok(None)
+            }
+
+            Message::Command(Command::Yank) => {
+                let Some(text) = shared::set(|shared| shared.kill_ring.yank().map(str::to_owned))
+                else {
+                    return Ok(Some(Message::notify(Level::Warn, "the kill ring is empty")));
+                };
+
+                let start = self.position()?;
+                self.insert_text(&text)?;
+                self.chain = Some(Chain::Yank(start));
+                self.touch();
+
+                Ok(None)
+            }
+
+            Message::Command(Command::YankPop) => {
+                let Some(Chain::Yank(start)) = chain else {
+                    return Ok(Some(Message::notify(
+                        Level::Warn,
+                        "the previous command was not a yank",
+                    )));
+                };
+
+                let end = self.position()?;
+                self.delete_range(start, end)?;
+                let text = shared::set(|shared| shared.kill_ring.rotate().map(str::to_owned));
+                self.insert_text(&text.unwrap_or_default())?;
+                self.chain = Some(Chain::Yank(start));
+                self.touch();
+
+                Ok(None)
+            }
+
+            Message::Command(Command::PageDown) => {
                 for _ in 0..self.lines.len() / 2 {
                     self.scroll_down()?;
                 }
@@ -502,7 +2218,7 @@ impl Portal {
                 Ok(None)
             }
 
-            pressed!(Key::PageUp) => {
+            Message::Command(Command::PageUp) => {
                 for _ in 0..self.lines.len() / 2 {
                     self.scroll_up()?;
                 }
@@ -511,7 +2227,7 @@ impl Portal {
             }
 
             Message::Input(Input::ScrollDown) => {
-                for _ in 0..SCROLL_DIST {
+                for _ in 0..self.settings.scroll_dist {
                     self.scroll_down()?;
                 }
 
@@ -519,7 +2235,7 @@ impl Portal {
             }
 
             Message::Input(Input::ScrollUp) => {
-                for _ in 0..SCROLL_DIST {
+                for _ in 0..self.settings.scroll_dist {
                     self.scroll_up()?;
                 }
 
@@ -530,44 +2246,112 @@ impl Portal {
         }
     }
 
-    pub fn status(&self, statuses: &mut StatusLine) -> Res {
-        match statuses {
-            StatusLine::Top(left, middle, right) => {
-                write!(left, "Buffer Top Left")?;
-                write!(middle, "Buffer Top")?;
-                write!(right, "Buffer Top Right")?;
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
+        line::with_tab_size(self.settings.tab_size, || self.write_status(fields))
+    }
 
-                Ok(())
-            }
-            StatusLine::Bottom(left, middle, right) => {
-                write!(left, "Buffer Bottom Left")?;
-                write!(middle, "Buffer Bottom")?;
-                write!(right, "Buffer Bottom Right")?;
-                Ok(())
-            }
+    fn write_status(&self, fields: &mut StatusFields) -> Res {
+        let line = self.offset + self.active + 1;
+        let column = self.current_line()?.correct_index(self.index).display() + 1;
+        let lines = self.line_count();
+        let name = match &self.source {
+            Source::File(path) => path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy(),
+            Source::Stdin => "stdin".into(),
+        };
+        let mode = if self.follower.is_some() {
+            "following"
+        } else if self.pager {
+            "pager"
+        } else if self.readonly {
+            "read-only"
+        } else {
+            ""
+        };
+
+        fields.set("path", self.name())?;
+        fields.set("name", name)?;
+        fields.set("dirty", if self.dirty { " [+]" } else { "" })?;
+        fields.set("mode", mode)?;
+        fields.set("line", line)?;
+        fields.set("col", column)?;
+        fields.set("lines", lines)?;
+        fields.set(
+            "size",
+            format_args!("{lines} line{}", if lines == 1 { "" } else { "s" }),
+        )?;
+        fields.set("encoding", self.format.encoding.name())?;
+        fields.set("ending", self.format.ending)?;
+        fields.set("format", self.format)?;
+        fields.set("position", format_args!("{line}:{column}"))?;
+        match &self.modal {
+            Some(modal) if modal.parser.pending().is_empty() => fields.set("modal", modal.mode)?,
+            Some(modal) => fields.set(
+                "modal",
+                format_args!("{} {}", modal.mode, modal.parser.pending()),
+            )?,
+            None => fields.set("modal", "")?,
         }
+
+        Ok(())
     }
 
     pub fn view(&self, out: &mut Out, active: bool) -> Res {
+        line::with_tab_size(self.settings.tab_size, || self.draw(out, active))
+    }
+
+    fn draw(&self, out: &mut Out, active: bool) -> Res {
+        if let Some((dialog, _)) = &self.dialog {
+            return dialog.view(out, self.bounds);
+        }
+
         out::anchor(out, self.bounds)?;
 
         let num_width = usize::from(self.line_num_width);
+        let text_x0 = self.bounds.x0 + self.line_num_width + 1;
+        let dim = shared::get(|shared| shared.config.colors.dim);
+        let selection = self.selection()?;
+        let inclusive = matches!(
+            self.modal,
+            Some(Modal {
+                mode: Mode::Visual,
+                ..
+            })
+        );
+        let highlight = |out: &mut Out, i: usize, line: &Line| -> Res {
+            let Some((from, to)) = selection else {
+                return Ok(());
+            };
+            let row = self.offset + i;
+            if row < from.0 || row > to.0 {
+                return Ok(());
+            }
+
+            let start = if row == from.0 { from.1.display() } else { 0 };
+            let end = if row != to.0 {
+                line.index_back(RawIndex::index_front())?.display() + 1
+            } else if inclusive {
+                line.index_forward(to.1)?
+                    .map_or(to.1.display() + 1, Index::display)
+            } else {
+                to.1.display()
+            };
+
+            line.highlight(out, text_x0, self.bounds.x1, start..end)
+        };
 
         for (i, line) in self.lines.iter().enumerate() {
             if i != self.active {
                 queue!(
                     out,
                     PrintStyledContent(
-                        style::style(format_args!("{:num_width$} ", self.offset + i))
-                            .with(style::Color::DarkGrey)
+                        style::style(format_args!("{:num_width$} ", self.offset + i)).with(dim)
                     ),
                 )?;
-                line.view(
-                    out,
-                    self.bounds.x0 + self.line_num_width + 1,
-                    self.bounds.x1,
-                    None,
-                )?;
+                line.view(out, text_x0, self.bounds.x1, None)?;
+                highlight(out, i, line)?;
             }
 
             queue!(out, MoveDown(1), MoveToColumn(self.bounds.x0))?;
@@ -591,7 +2375,7 @@ impl Portal {
         )?;
         self.current_line()?.view(
             out,
-            self.bounds.x0 + self.line_num_width + 1,
+            text_x0,
             self.bounds.x1,
             if active {
                 Some(self.current_line()?.correct_index(self.index))
@@ -599,7 +2383,255 @@ impl Portal {
                 None
             },
         )?;
+        highlight(out, self.active, self.current_line()?)?;
+
+        if let (true, Some(modal)) = (active, &self.modal) {
+            let shape = match modal.mode {
+                Mode::Insert => SetCursorStyle::BlinkingBar,
+                Mode::Normal | Mode::Visual => SetCursorStyle::BlinkingBlock,
+            };
+            queue!(out, shape)?;
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MAX_SCROLL_GRACE;
+    use std::{env, process};
+
+    fn portal(text: &str) -> Portal {
+        let lines = text.split('\n').map(Into::into).collect();
+        let bounds = Bounds {
+            x0: 0,
+            y0: 0,
+            x1: 80,
+            y1: 24,
+        };
+
+        Portal::new(Source::Stdin, lines, Format::default(), bounds).unwrap()
+    }
+
+    fn modal(text: &str) -> Portal {
+        shared::set(|shared| shared.config.editor.modal = true);
+
+        portal(text)
+    }
+
+    fn run(portal: &mut Portal, commands: &[Command]) {
+        for &command in commands {
+            portal.update(&Message::Command(command)).unwrap();
+        }
+    }
+
+    fn feed(portal: &mut Portal, keys: &str) {
+        for c in keys.chars() {
+            let message = match c {
+                '\x1b' => Message::Command(Command::NormalMode),
+                _ => Message::Input(Input::KeyCombo(KeyCombo {
+                    key: Key::Char(c),
+                    shift: c.is_uppercase(),
+                    ctrl: false,
+                    alt: false,
+                    super_: false,
+                })),
+            };
+            portal.update(&message).unwrap();
+        }
+    }
+
+    fn edit(text: &str, keys: &str) -> String {
+        let mut portal = modal(text);
+        feed(&mut portal, keys);
+
+        portal.text()
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(edit("one two three", "dw"), "two three\n");
+        assert_eq!(edit("one two three", "wd$"), "one \n");
+        assert_eq!(edit("a\nb\nc\nd", "jd2j"), "a\n");
+        assert_eq!(edit("a\nb\nc", "jyyjp"), "a\nb\nc\nb\n");
+    }
+
+    #[test]
+    fn delete_lines_at_the_end() {
+        assert_eq!(edit("a\nb\nc", "Gdd"), "a\nb\n");
+        assert_eq!(edit("a\nb\nc", "jdd"), "a\nc\n");
+        assert_eq!(edit("a", "dd"), "\n");
+    }
+
+    #[test]
+    fn delete_ranges_across_lines() {
+        assert_eq!(edit("one\ntwo three", "wd2w"), "one\n\n");
+        assert_eq!(edit("one\ntwo\nthree", "lvjd"), "oo\nthree\n");
+    }
+
+    #[test]
+    fn change_word_stops_at_the_word_end() {
+        assert_eq!(edit("one two", "cwsix\x1b"), "six two\n");
+        assert_eq!(edit("one two", "c2wx\x1b"), "x\n");
+        assert_eq!(edit("one two", "llcwx\x1b"), "onx two\n");
+        assert_eq!(edit("a b c", "cwx\x1b"), "x b c\n");
+        assert_eq!(edit("one  two", "lllcwx\x1b"), "onextwo\n");
+    }
+
+    #[test]
+    fn paste() {
+        assert_eq!(edit("a\nb", "yyp"), "a\na\nb\n");
+        assert_eq!(edit("a\nb", "jyyP"), "a\nb\nb\n");
+        assert_eq!(edit("ab", "ylp"), "aab\n");
+        assert_eq!(edit("ab", "lylP"), "abb\n");
+        assert_eq!(edit("ab", "yl3p"), "aaaab\n");
+        assert_eq!(edit("a\nb", "yy2P"), "a\na\na\nb\n");
+    }
+
+    #[test]
+    fn repeat() {
+        assert_eq!(edit("abcdef", "x.."), "def\n");
+        assert_eq!(edit("abcdef", "x2."), "def\n");
+        assert_eq!(edit("a b c", "cwx\x1bw."), "x x c\n");
+        assert_eq!(edit("a b c", "wwcwx\x1b"), "a b x\n");
+        assert_eq!(edit("a b c", "cwx\x1bw.w."), "x x x\n");
+        assert_eq!(edit("a\nb\nc\nd", "dd."), "c\nd\n");
+    }
+
+    #[test]
+    fn huge_counts() {
+        assert_eq!(edit("a\nb\nc", "99999999999999dd"), "\n");
+        assert_eq!(edit("a\nb\nc", "j99999999999999ddx"), "\n");
+        assert_eq!(edit("a\nb\nc", "99999999999999jx"), "a\nb\n\n");
+        assert_eq!(edit("ab", "l99999999999999x"), "a\n");
+        assert_eq!(edit("abc", "x99999999999999."), "\n");
+        assert_eq!(edit("a\nb", "yy2p"), "a\na\na\nb\n");
+
+        let line = "a".repeat(1000);
+        assert_eq!(
+            edit(&line, "yy9999999p").lines().count(),
+            MAX_PASTE / 1000 + 1
+        );
+    }
+
+    #[test]
+    fn consecutive_kills_append() {
+        let mut portal = portal("one\ntwo\nthree");
+        run(&mut portal, &[Command::KillLine, Command::KillLine]);
+        assert_eq!(portal.text(), "two\nthree\n");
+        assert!(portal.dirty);
+
+        run(
+            &mut portal,
+            &[Command::CursorDown, Command::KillLine, Command::Yank],
+        );
+        assert_eq!(portal.text(), "two\nthree\n");
+        run(&mut portal, &[Command::Top, Command::Yank]);
+        assert_eq!(portal.text(), "threetwo\nthree\n");
+    }
+
+    #[test]
+    fn yank_pop_cycles_kills() {
+        let mut portal = portal("a\nb\nc");
+        run(
+            &mut portal,
+            &[
+                Command::KillLine,
+                Command::CursorDown,
+                Command::KillLine,
+                Command::Bottom,
+                Command::Yank,
+            ],
+        );
+        assert_eq!(portal.text(), "\n\ncb\n");
+
+        run(&mut portal, &[Command::YankPop]);
+        assert_eq!(portal.text(), "\n\nca\n");
+        run(&mut portal, &[Command::YankPop]);
+        assert_eq!(portal.text(), "\n\ncb\n");
+
+        run(&mut portal, &[Command::CursorLeft, Command::YankPop]);
+        assert_eq!(portal.text(), "\n\ncb\n");
+    }
+
+    #[test]
+    fn noop_kills_and_yanks_keep_the_buffer_clean() {
+        let mut portal = portal("a");
+        run(
+            &mut portal,
+            &[Command::Yank, Command::LineEnd, Command::KillLine],
+        );
+        assert_eq!(portal.text(), "a\n");
+        assert!(!portal.dirty);
+
+        run(&mut portal, &[Command::LineStart, Command::KillLine]);
+        assert_eq!(portal.text(), "\n");
+        assert!(portal.dirty);
+    }
+
+    #[test]
+    fn settings_change_only_on_reload() {
+        let mut portal = portal("a");
+        shared::set(|shared| shared.config.editor.modal = true);
+        run(&mut portal, &[Command::LineEnd]);
+        portal.tick(&mut vec![]).unwrap();
+        assert!(portal.modal.is_none());
+
+        run(&mut portal, &[Command::ReloadConfig]);
+        assert!(portal.modal.is_some());
+
+        // A tile can be shorter than a valid grace, and the view must still reach both ends.
+        let text: Vec<_> = (0..100).map(|i| i.to_string()).collect();
+        let line = |portal: &Portal| String::from(portal.current_line().unwrap().as_ref());
+        for grace in [1, MAX_SCROLL_GRACE] {
+            let mut portal = self::portal(&text.join("\n"));
+            shared::set(|shared| shared.config.editor.scroll_grace = grace);
+            run(&mut portal, &[Command::ReloadConfig]);
+            assert_eq!(portal.settings.scroll_grace, grace);
+
+            run(&mut portal, &[Command::CursorDown; 99]);
+            assert_eq!(line(&portal), "99");
+            run(&mut portal, &[Command::CursorUp; 99]);
+            assert_eq!(line(&portal), "0");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn opens_unwritable_files_read_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = env::temp_dir().join(format!("neonano-readonly-{}.txt", process::id()));
+        fs::write(&path, "a\n").unwrap();
+        let writable = Portal::open(&path, portal("").bounds);
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o444)).unwrap();
+        let unwritable = Portal::open(&path, portal("").bounds);
+        fs::remove_file(&path).unwrap();
+
+        assert!(!writable.unwrap().readonly);
+        // SAFETY: geteuid has no preconditions.
+        let root = unsafe { libc::geteuid() } == 0;
+        assert_eq!(unwritable.unwrap().readonly, !root);
+    }
+
+    #[test]
+    fn charset_only_sets_the_write_encoding() {
+        let dir = env::temp_dir().join(format!("neonano-charset-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(".editorconfig"),
+            "root = true\n[*]\ncharset = latin1\n",
+        )
+        .unwrap();
+        fs::write(dir.join("a.txt"), "h\u{e9}llo\n").unwrap();
+
+        let portal = Portal::open(dir.join("a.txt"), portal("").bounds);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let portal = portal.unwrap();
+        assert_eq!(portal.text(), "h\u{e9}llo\n");
+        assert_eq!(portal.format.encoding, encoding_rs::WINDOWS_1252);
+    }
+}
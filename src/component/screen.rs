@@ -1,13 +1,29 @@
 use crate::{
-    component::{filepicker::FilePicker, frame::StatusLine, portal::Portal},
+    args::{Args, Position, Target},
+    command::Command,
+    component::{
+        filepicker::FilePicker,
+        frame::StatusFields,
+        hex::Hex,
+        largefile::LargeFile,
+        logviewer::LogViewer,
+        portal::{Binary, Portal},
+    },
     core::Res,
-    message::{Key, Message},
-    pressed,
-    utils::out::{self, Bounds, Out},
+    keymap,
+    message::Message,
+    utils::{
+        log::Level,
+        out::{self, Bounds, Out},
+    },
 };
 use anyhow::Context;
 use crossterm::{cursor::MoveTo, queue, style::Print};
-use std::io::{self, ErrorKind};
+use std::{fs, io, path::Path};
+
+const LARGE_FILE_SIZE: u64 = 64 * 1024 * 1024;
+const MAX_TILES: usize = 3;
+const MIN_TILE_HEIGHT: u16 = 3;
 
 #[derive(Clone, Debug)]
 pub struct Screen {
@@ -17,7 +33,7 @@ pub struct Screen {
 }
 
 impl Screen {
-    pub fn new(bounds: Bounds) -> Res<Self> {
+    pub fn new(bounds: Bounds, args: &Args) -> Res<Self> {
         let bordered = Bounds {
             x0: bounds.x0 + 1,
             y0: bounds.y0 + 1,
@@ -26,7 +42,7 @@ impl Screen {
         };
 
         Ok(Self {
-            columns: [Some(Column::new(bordered)?), None, None],
+            columns: [Some(Column::new(bordered, args)?), None, None],
             active: 0,
             bounds,
         })
@@ -40,6 +56,35 @@ impl Screen {
         self.columns().count()
     }
 
+    pub fn portals(&self) -> impl Iterator<Item = &Portal> {
+        self.columns().flat_map(Column::portals)
+    }
+
+    pub fn contents(&self) -> impl Iterator<Item = &Content> {
+        self.columns().flat_map(Column::contents)
+    }
+
+    pub fn contents_mut(&mut self) -> impl Iterator<Item = &mut Content> {
+        self.columns
+            .iter_mut()
+            .flatten()
+            .flat_map(Column::contents_mut)
+    }
+
+    pub fn active_content_mut(&mut self) -> Option<&mut Content> {
+        self.columns[self.active]
+            .as_mut()
+            .and_then(Column::active_content_mut)
+    }
+
+    pub fn broadcast(&mut self, message: &Message) -> Res {
+        for content in self.contents_mut() {
+            content.update(message)?;
+        }
+
+        Ok(())
+    }
+
     pub fn update(&mut self, message: &Message) -> Res<Option<Message>> {
         self.columns[self.active]
             .as_mut()
@@ -47,11 +92,27 @@ impl Screen {
             .update(message)
     }
 
-    pub fn status(&self, statuses: &mut StatusLine) -> Res {
+    pub fn context(&self) -> keymap::Context {
+        self.columns[self.active]
+            .as_ref()
+            .map_or(keymap::Context::Global, Column::context)
+    }
+
+    pub fn tick(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        self.columns
+            .iter_mut()
+            .flatten()
+            .try_fold(
+                false,
+                |updated, column| Ok(column.tick(notices)? || updated),
+            )
+    }
+
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
         self.columns[self.active]
             .as_ref()
             .context("column should be Some")?
-            .status(statuses)
+            .status(fields)
     }
 
     pub fn view(&self, out: &mut Out) -> Res {
@@ -109,18 +170,88 @@ impl Screen {
 
 #[derive(Clone, Debug)]
 struct Column {
-    tiles: [Option<Tile>; 3],
+    tiles: [Option<Tile>; MAX_TILES],
     active: usize,
+    bounds: Bounds,
 }
 
 impl Column {
-    fn new(bounds: Bounds) -> Res<Self> {
+    fn new(bounds: Bounds, args: &Args) -> Res<Self> {
         Ok(Self {
-            tiles: [Some(Tile::new(bounds)?), None, None],
+            tiles: [Some(Tile::new(bounds, args)?), None, None],
             active: 0,
+            bounds,
         })
     }
 
+    // Tiles are stacked with a separator row between them, on the rows where the screen border
+    // draws its junctions.
+    fn layout(&self, count: usize) -> Option<Vec<Bounds>> {
+        let count = u16::try_from(count).ok()?;
+        let interval = (self.bounds.height() + 2) / count;
+        if interval <= MIN_TILE_HEIGHT {
+            return None;
+        }
+
+        let mut y0 = self.bounds.y0;
+        let layout = (1..=count)
+            .map(|i| {
+                let y1 = match i == count {
+                    true => self.bounds.y1,
+                    false => self.bounds.y0 + interval * i - 2,
+                };
+                let bounds = Bounds {
+                    y0,
+                    y1,
+                    ..self.bounds
+                };
+                y0 = y1 + 1;
+
+                bounds
+            })
+            .collect();
+
+        Some(layout)
+    }
+
+    fn relayout(&mut self) -> Res {
+        let layout = self.layout(self.len()).context("tiles fit the column")?;
+
+        for (tile, bounds) in self.tiles.iter_mut().flatten().zip(layout) {
+            tile.set_bounds(bounds)?;
+        }
+
+        Ok(())
+    }
+
+    fn split(&mut self) -> Res<Option<Message>> {
+        if self.len() == MAX_TILES || self.layout(self.len() + 1).is_none() {
+            return Ok(Some(Message::notify(
+                Level::Warn,
+                "there is no room to split this tile",
+            )));
+        }
+
+        let tile = Tile {
+            content: vec![Content::new(self.bounds)?],
+            active: 0,
+        };
+        self.active += 1;
+        self.tiles[self.active..].rotate_right(1);
+        self.tiles[self.active] = Some(tile);
+        self.relayout()?;
+
+        Ok(None)
+    }
+
+    fn remove_active(&mut self) -> Res {
+        self.tiles[self.active..].rotate_left(1);
+        self.tiles[MAX_TILES - 1] = None;
+        self.active = self.active.min(self.len() - 1);
+
+        self.relayout()
+    }
+
     fn tiles(&self) -> impl Iterator<Item = &Tile> {
         self.tiles.iter().flatten()
     }
@@ -129,21 +260,84 @@ impl Column {
         self.tiles().count()
     }
 
+    fn portals(&self) -> impl Iterator<Item = &Portal> {
+        self.tiles().flat_map(Tile::portals)
+    }
+
+    fn contents(&self) -> impl Iterator<Item = &Content> {
+        self.tiles().flat_map(|tile| tile.content.iter())
+    }
+
+    fn contents_mut(&mut self) -> impl Iterator<Item = &mut Content> {
+        self.tiles
+            .iter_mut()
+            .flatten()
+            .flat_map(|tile| tile.content.iter_mut())
+    }
+
+    fn active_content_mut(&mut self) -> Option<&mut Content> {
+        let tile = self.tiles[self.active].as_mut()?;
+
+        tile.content.get_mut(tile.active)
+    }
+
     fn update(&mut self, message: &Message) -> Res<Option<Message>> {
+        let tile = self.tiles[self.active]
+            .as_ref()
+            .context("tile should be Some")?;
+        let empty = matches!(tile.content[tile.active], Content::FilePicker(_));
+
+        match message {
+            Message::Command(Command::TileSplit) => self.split(),
+
+            Message::Command(Command::TileFocus) => {
+                self.active = (self.active + 1) % self.len();
+
+                Ok(None)
+            }
+
+            // Closing a tile that has nothing left to close removes it from the split.
+            Message::Command(Command::Close) if empty && self.len() > 1 => {
+                self.remove_active()?;
+
+                Ok(None)
+            }
+
+            _ => self.tiles[self.active]
+                .as_mut()
+                .context("tile should be Some")?
+                .update(message),
+        }
+    }
+
+    fn context(&self) -> keymap::Context {
         self.tiles[self.active]
-            .as_mut()
-            .context("tile should be Some")?
-            .update(message)
+            .as_ref()
+            .map_or(keymap::Context::Global, Tile::context)
     }
 
-    pub fn status(&self, statuses: &mut StatusLine) -> Res {
+    fn tick(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        self.tiles
+            .iter_mut()
+            .flatten()
+            .try_fold(false, |updated, tile| Ok(tile.tick(notices)? || updated))
+    }
+
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
         self.tiles[self.active]
             .as_ref()
             .context("tile should be Some")?
-            .status(statuses)
+            .status(fields)
     }
 
     fn view(&self, out: &mut Out, active: bool) -> Res {
+        // Buffers leave their last column blank, so the separators do too.
+        let width = usize::from(self.bounds.width()).saturating_sub(1);
+        for tile in self.tiles().take(self.len() - 1) {
+            let bounds = tile.bounds();
+            queue!(out, MoveTo(bounds.x0, bounds.y1), Print("─".repeat(width)))?;
+        }
+
         let inactive_tiles = self
             .tiles()
             .enumerate()
@@ -172,19 +366,90 @@ struct Tile {
 }
 
 impl Tile {
-    fn new(bounds: Bounds) -> Res<Self> {
-        Ok(Self {
-            content: vec![Content::new(bounds)?],
-            active: 0,
+    fn new(bounds: Bounds, args: &Args) -> Res<Self> {
+        let content = if args.targets.is_empty() {
+            vec![Content::new(bounds)?]
+        } else {
+            args.targets
+                .iter()
+                .map(|target| Content::open(target, args, bounds))
+                .collect::<Res<_>>()?
+        };
+
+        Ok(Self { content, active: 0 })
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.content[self.active].bounds()
+    }
+
+    fn set_bounds(&mut self, bounds: Bounds) -> Res {
+        self.content
+            .iter_mut()
+            .try_for_each(|content| content.set_bounds(bounds))
+    }
+
+    fn portals(&self) -> impl Iterator<Item = &Portal> {
+        self.content.iter().filter_map(|content| match content {
+            Content::Portal(portal) => Some(portal.as_ref()),
+            _ => None,
         })
     }
 
     fn update(&mut self, message: &Message) -> Res<Option<Message>> {
-        self.content[self.active].update(message)
+        match message {
+            Message::Command(Command::TileNext) if self.content.len() > 1 => {
+                self.active = (self.active + 1) % self.content.len();
+
+                Ok(None)
+            }
+
+            Message::Command(Command::TilePrev) if self.content.len() > 1 => {
+                self.active = self.active.checked_sub(1).unwrap_or(self.content.len() - 1);
+
+                Ok(None)
+            }
+
+            Message::Command(Command::TileLog) => {
+                let log = self
+                    .content
+                    .iter()
+                    .position(|content| matches!(content, Content::Log(_)));
+
+                self.active = log.unwrap_or_else(|| {
+                    let bounds = self.content[self.active].bounds();
+                    self.content.push(Content::Log(LogViewer::new(bounds)));
+                    self.content.len() - 1
+                });
+
+                Ok(None)
+            }
+
+            Message::Command(Command::Close)
+                if matches!(self.content[self.active], Content::Log(_)) =>
+            {
+                self.content.remove(self.active);
+                self.active = self.active.saturating_sub(1);
+
+                Ok(None)
+            }
+
+            _ => self.content[self.active].update(message),
+        }
     }
 
-    pub fn status(&self, statuses: &mut StatusLine) -> Res {
-        self.content[self.active].status(statuses)
+    fn context(&self) -> keymap::Context {
+        self.content[self.active].context()
+    }
+
+    fn tick(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        self.content.iter_mut().try_fold(false, |updated, content| {
+            Ok(content.tick(notices)? || updated)
+        })
+    }
+
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
+        self.content[self.active].status(fields)
     }
 
     fn view(&self, out: &mut Out, active: bool) -> Res {
@@ -195,7 +460,10 @@ impl Tile {
 #[derive(Clone, Debug)]
 pub enum Content {
     FilePicker(FilePicker),
-    Portal(Portal),
+    Portal(Box<Portal>),
+    Hex(Hex),
+    Large(LargeFile),
+    Log(LogViewer),
 }
 
 impl Content {
@@ -203,32 +471,151 @@ impl Content {
         Ok(Self::FilePicker(FilePicker::new(bounds)?))
     }
 
-    fn update(&mut self, message: &Message) -> Res<Option<Message>> {
-        match message {
-            pressed!(Key::Esc) => match self {
-                Self::Portal(portal) => {
-                    let filepicker = FilePicker::new(portal.bounds())?;
+    fn bounds(&self) -> Bounds {
+        match self {
+            Self::FilePicker(filepicker) => filepicker.bounds(),
+            Self::Portal(portal) => portal.bounds(),
+            Self::Hex(hex) => hex.bounds(),
+            Self::Large(large) => large.bounds(),
+            Self::Log(log) => log.bounds(),
+        }
+    }
+
+    fn set_bounds(&mut self, bounds: Bounds) -> Res {
+        match self {
+            Self::FilePicker(filepicker) => filepicker.set_bounds(bounds),
+            Self::Portal(portal) => portal.set_bounds(bounds)?,
+            Self::Hex(hex) => hex.set_bounds(bounds),
+            Self::Large(large) => large.set_bounds(bounds)?,
+            Self::Log(log) => log.set_bounds(bounds),
+        }
+
+        Ok(())
+    }
+
+    pub fn recoverable(&self) -> Option<String> {
+        match self {
+            Self::Portal(portal) if portal.is_dirty() => Some(portal.name()),
+            Self::Hex(hex) if hex.is_dirty() => Some(hex.name()),
+            _ => None,
+        }
+    }
+
+    pub fn write_to(&self, writer: impl io::Write) -> Res {
+        match self {
+            Self::Portal(portal) => portal.write_to(writer),
+            Self::Hex(hex) => hex.write_to(writer),
+            Self::FilePicker(_) | Self::Large(_) | Self::Log(_) => Ok(()),
+        }
+    }
+
+    pub fn unsaved(&self) -> Option<String> {
+        match self {
+            Self::Portal(portal) if portal.is_dirty() && !portal.is_stdin() => Some(portal.name()),
+            Self::Hex(hex) if hex.is_dirty() => Some(hex.name()),
+            _ => None,
+        }
+    }
 
-                    *self = Self::FilePicker(filepicker);
+    pub fn save(&mut self) -> Res<Option<Message>> {
+        self.update(&Message::Command(Command::Save))
+    }
+
+    pub fn discard(&mut self) -> Res {
+        match self {
+            Self::Portal(portal) => portal.discard(),
+            Self::Hex(hex) => {
+                hex.discard();
+                Ok(())
+            }
+            Self::FilePicker(_) | Self::Large(_) | Self::Log(_) => Ok(()),
+        }
+    }
+
+    fn open(target: &Target, args: &Args, bounds: Bounds) -> Res<Self> {
+        match target {
+            Target::Dir(dir) => Ok(Self::FilePicker(FilePicker::in_dir(dir, bounds)?)),
+
+            Target::Stdin => {
+                let mut portal = Portal::stdin(bounds).context("failed to read stdin")?;
+
+                portal.set_readonly(args.readonly);
+                portal.set_pager(args.pager);
+
+                Ok(Self::Portal(Box::new(portal)))
+            }
+
+            Target::File { path, position } => {
+                let mut content = Self::open_file(path, args, bounds)
+                    .with_context(|| format!("failed to open {}", path.display()))?;
 
-                    Ok(None)
+                if let (Self::Portal(portal), &Some(Position { line, column })) =
+                    (&mut content, position)
+                {
+                    portal.goto(line, column)?;
                 }
 
-                Self::FilePicker(filepicker) => filepicker.update(message),
-            },
+                Ok(content)
+            }
+        }
+    }
+
+    fn open_file(path: &Path, args: &Args, bounds: Bounds) -> Res<Self> {
+        if fs::metadata(path).is_ok_and(|metadata| metadata.len() >= LARGE_FILE_SIZE) {
+            let mut large = LargeFile::open(path, bounds)?;
+            large.set_follow(args.follow);
+
+            return Ok(Self::Large(large));
+        }
+
+        match Portal::open(path, bounds) {
+            Ok(mut portal) => {
+                if args.readonly {
+                    portal.set_readonly(true);
+                }
+                portal.set_pager(args.pager);
+                if args.follow {
+                    portal.set_follow(true)?;
+                }
+
+                Ok(Self::Portal(Box::new(portal)))
+            }
+            Err(error) if error.is::<Binary>() => {
+                let mut hex = Hex::open(path, bounds)?;
+                hex.set_readonly(args.readonly || args.pager);
+
+                Ok(Self::Hex(hex))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn update(&mut self, message: &Message) -> Res<Option<Message>> {
+        match message {
+            Message::Command(Command::Close) => {
+                if let Self::Large(large) = self {
+                    if let Some(cancelled) = large.cancel() {
+                        return Ok(Some(cancelled));
+                    }
+                }
+                if let Self::Portal(_) | Self::Hex(_) | Self::Large(_) = self {
+                    *self = Self::FilePicker(FilePicker::new(self.bounds())?);
+                }
+
+                Ok(None)
+            }
 
             Message::Open(path) => {
                 if let Self::FilePicker(filepicker) = self {
-                    match Portal::open(path, filepicker.bounds()) {
-                        Ok(buffer) => {
-                            *self = Self::Portal(buffer);
+                    match Self::open_file(path, &Args::default(), filepicker.bounds()) {
+                        Ok(content) => {
+                            *self = content;
                         }
                         Err(error) => {
-                            let io_error: &io::Error =
-                                error.downcast_ref().context("unknown error")?;
-                            if io_error.kind() != ErrorKind::InvalidData {
-                                return Err(error);
-                            }
+                            return Ok(Some(Message::notify(
+                                Level::Error,
+                                format!("cannot open {}: {error:#}", path.display()),
+                            )));
                         }
                     }
                 }
@@ -238,22 +625,47 @@ impl Content {
 
             _ => match self {
                 Content::Portal(buffer) => buffer.update(message),
+                Content::Hex(hex) => hex.update(message),
+                Content::Large(large) => large.update(message),
                 Content::FilePicker(filepicker) => filepicker.update(message),
+                Content::Log(log) => log.update(message),
             },
         }
     }
 
-    fn status(&self, statuses: &mut StatusLine) -> Res {
+    fn context(&self) -> keymap::Context {
+        match self {
+            Self::FilePicker(_) => keymap::Context::FilePicker,
+            Self::Portal(portal) => portal.context(),
+            Self::Hex(_) | Self::Large(_) | Self::Log(_) => keymap::Context::Viewer,
+        }
+    }
+
+    fn tick(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        match self {
+            Content::Portal(portal) => portal.tick(notices),
+            Content::Large(large) => large.tick(notices),
+            Content::FilePicker(_) | Content::Hex(_) | Content::Log(_) => Ok(false),
+        }
+    }
+
+    fn status(&self, fields: &mut StatusFields) -> Res {
         match self {
-            Content::FilePicker(filepicker) => filepicker.status(statuses),
-            Content::Portal(buffer) => buffer.status(statuses),
+            Content::FilePicker(filepicker) => filepicker.status(fields),
+            Content::Portal(buffer) => buffer.status(fields),
+            Content::Hex(hex) => hex.status(fields),
+            Content::Large(large) => large.status(fields),
+            Content::Log(log) => log.status(fields),
         }
     }
 
     fn view(&self, out: &mut Out, active: bool) -> Res {
         match self {
             Content::Portal(buffer) => buffer.view(out, active),
+            Content::Hex(hex) => hex.view(out, active),
+            Content::Large(large) => large.view(out, active),
             Content::FilePicker(filepicker) => filepicker.view(out, active),
+            Content::Log(log) => log.view(out, active),
         }
     }
 }
@@ -1,6 +1,12 @@
 use crate::{
-    component::{frame::StatusLine, screen::Screen},
+    args::Args,
+    component::{
+        frame::StatusFields,
+        portal::Portal,
+        screen::{Content, Screen},
+    },
     core::Res,
+    keymap::Context,
     message::Message,
     utils::out::{Bounds, Out},
 };
@@ -12,9 +18,9 @@ pub struct Window {
 }
 
 impl Window {
-    pub fn new(bounds: Bounds) -> Res<Self> {
+    pub fn new(bounds: Bounds, args: &Args) -> Res<Self> {
         Ok(Self {
-            screens: vec![Screen::new(bounds)?],
+            screens: vec![Screen::new(bounds, args)?],
             active: 0,
         })
     }
@@ -32,8 +38,42 @@ impl Window {
         }
     }
 
-    pub fn status(&self, statuses: &mut StatusLine) -> Res {
-        self.screens[self.active].status(statuses)
+    pub fn broadcast(&mut self, message: &Message) -> Res {
+        for screen in &mut self.screens {
+            screen.broadcast(message)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn context(&self) -> Context {
+        self.screens[self.active].context()
+    }
+
+    pub fn tick(&mut self, notices: &mut Vec<Message>) -> Res<bool> {
+        self.screens.iter_mut().try_fold(false, |updated, screen| {
+            Ok(screen.tick(notices)? || updated)
+        })
+    }
+
+    pub fn portals(&self) -> impl Iterator<Item = &Portal> {
+        self.screens.iter().flat_map(Screen::portals)
+    }
+
+    pub fn contents(&self) -> impl Iterator<Item = &Content> {
+        self.screens.iter().flat_map(Screen::contents)
+    }
+
+    pub fn contents_mut(&mut self) -> impl Iterator<Item = &mut Content> {
+        self.screens.iter_mut().flat_map(Screen::contents_mut)
+    }
+
+    pub fn active_content_mut(&mut self) -> Option<&mut Content> {
+        self.screens[self.active].active_content_mut()
+    }
+
+    pub fn status(&self, fields: &mut StatusFields) -> Res {
+        self.screens[self.active].status(fields)
     }
 
     pub fn view(&self, out: &mut Out) -> Res {
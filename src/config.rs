@@ -0,0 +1,313 @@
+use crate::{core::Res, utils::dirs};
+use anyhow::{anyhow, bail, Context};
+use crossterm::style::Color;
+use serde::{de, Deserialize, Deserializer};
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+pub const MAX_TAB_SIZE: usize = 16;
+pub const MAX_SCROLL_GRACE: usize = 32;
+
+#[derive(Clone, Default, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub statusline: StatusLineConfig,
+    pub editor: EditorConfig,
+    pub picker: PickerConfig,
+    pub colors: ColorConfig,
+    pub macros: MacroConfig,
+    pub filetype: HashMap<String, FileTypeConfig>,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditorConfig {
+    pub modal: bool,
+    pub tab_size: usize,
+    pub scroll_grace: usize,
+    pub scroll_dist: usize,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            modal: false,
+            tab_size: 4,
+            scroll_grace: 3,
+            scroll_dist: 5,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileTypeConfig {
+    pub tab_size: Option<usize>,
+    pub scroll_grace: Option<usize>,
+    pub scroll_dist: Option<usize>,
+}
+
+#[derive(Clone, Default, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct MacroConfig {
+    pub persist: bool,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PickerConfig {
+    pub dir_icon: char,
+    pub file_icon: char,
+}
+
+impl Default for PickerConfig {
+    fn default() -> Self {
+        Self {
+            dir_icon: '📂',
+            file_icon: '📄',
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorConfig {
+    #[serde(deserialize_with = "color")]
+    pub text: Color,
+    #[serde(deserialize_with = "color")]
+    pub dim: Color,
+    #[serde(deserialize_with = "color")]
+    pub highlight_text: Color,
+    #[serde(deserialize_with = "color")]
+    pub highlight: Color,
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        Self {
+            text: Color::White,
+            dim: Color::DarkGrey,
+            highlight_text: Color::Black,
+            highlight: Color::White,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusLineConfig {
+    pub top: String,
+    pub bottom: String,
+}
+
+impl Default for StatusLineConfig {
+    fn default() -> Self {
+        Self {
+            top: "{path}{dirty} | {modal} {macro} | {mode}".into(),
+            bottom: "{position} | {size} | {format}".into(),
+        }
+    }
+}
+
+impl Config {
+    pub fn editor_for(&self, path: Option<&Path>) -> EditorConfig {
+        let mut editor = self.editor.clone();
+        let overrides = path.and_then(|path| {
+            let by_name = path
+                .file_name()
+                .and_then(|name| self.filetype.get(name.to_str()?));
+
+            by_name.or_else(|| self.filetype.get(path.extension()?.to_str()?))
+        });
+
+        if let Some(overrides) = overrides {
+            editor.tab_size = overrides.tab_size.unwrap_or(editor.tab_size);
+            editor.scroll_grace = overrides.scroll_grace.unwrap_or(editor.scroll_grace);
+            editor.scroll_dist = overrides.scroll_dist.unwrap_or(editor.scroll_dist);
+        }
+
+        editor
+    }
+
+    fn validate(&self) -> Res {
+        let check = |section: &str,
+                     tab_size: Option<usize>,
+                     scroll_grace: Option<usize>,
+                     scroll_dist: Option<usize>| {
+            if tab_size.is_some_and(|size| !(1..=MAX_TAB_SIZE).contains(&size)) {
+                bail!("{section}.tab_size must be between 1 and {MAX_TAB_SIZE}");
+            }
+            if scroll_grace.is_some_and(|grace| !(1..=MAX_SCROLL_GRACE).contains(&grace)) {
+                bail!("{section}.scroll_grace must be between 1 and {MAX_SCROLL_GRACE}");
+            }
+            if scroll_dist == Some(0) {
+                bail!("{section}.scroll_dist must be at least 1");
+            }
+
+            Ok(())
+        };
+
+        check(
+            "editor",
+            Some(self.editor.tab_size),
+            Some(self.editor.scroll_grace),
+            Some(self.editor.scroll_dist),
+        )?;
+        for (name, filetype) in &self.filetype {
+            check(
+                &format!("filetype.{name}"),
+                filetype.tab_size,
+                filetype.scroll_grace,
+                filetype.scroll_dist,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+        return match hex.len() {
+            6 => Some(Color::Rgb {
+                r: channel(0)?,
+                g: channel(2)?,
+                b: channel(4)?,
+            }),
+            _ => None,
+        };
+    }
+
+    match name {
+        "reset" | "default" => Some(Color::Reset),
+        _ => Color::try_from(name).ok(),
+    }
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let name = String::deserialize(deserializer)?;
+
+    parse_color(&name).ok_or_else(|| de::Error::custom(format!("unknown color '{name}'")))
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("config.toml"))
+}
+
+pub fn load() -> Res<Config> {
+    let Some(path) = path() else {
+        return Ok(Config::default());
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            let config: Config = toml::from_str(&text).map_err(|error| {
+                let line = error
+                    .span()
+                    .map_or(1, |span| text[..span.start].matches('\n').count() + 1);
+
+                anyhow!(
+                    "invalid config {}:{line}: {}",
+                    path.display(),
+                    error.message()
+                )
+            })?;
+            config
+                .validate()
+                .map_err(|error| anyhow!("invalid config {}: {error}", path.display()))?;
+
+            Ok(config)
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Config::default()),
+        Err(error) => {
+            Err(error).with_context(|| format!("failed to read config {}", path.display()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_config() {
+        let config: Config = toml::from_str("[statusline]\ntop = \"{path}\"").unwrap();
+
+        assert_eq!(config.statusline.top, "{path}");
+        assert_eq!(config.statusline.bottom, StatusLineConfig::default().bottom);
+    }
+
+    #[test]
+    fn editor_settings() {
+        let config: Config = toml::from_str("[editor]\nmodal = true").unwrap();
+
+        assert!(config.editor.modal);
+        assert!(!Config::default().editor.modal);
+        assert_eq!(config.editor.tab_size, 4);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>("[statusline]\ncenter = \"\"").is_err());
+        assert!(toml::from_str::<Config>("[filetype.rs]\nwidth = 2").is_err());
+    }
+
+    #[test]
+    fn colors() {
+        let config: Config =
+            toml::from_str("[colors]\ndim = \"dark_blue\"\nhighlight = \"#ff8000\"").unwrap();
+
+        assert_eq!(config.colors.dim, Color::DarkBlue);
+        assert_eq!(
+            config.colors.highlight,
+            Color::Rgb {
+                r: 0xff,
+                g: 0x80,
+                b: 0
+            }
+        );
+        assert_eq!(config.colors.text, Color::White);
+
+        let error = toml::from_str::<Config>("[colors]\ntext = \"mauve\"").unwrap_err();
+        assert!(error.message().contains("unknown color 'mauve'"));
+        assert!(toml::from_str::<Config>("[colors]\ntext = \"#12345\"").is_err());
+    }
+
+    #[test]
+    fn filetype_overrides() {
+        let config: Config = toml::from_str(
+            "[editor]\ntab_size = 8\n[filetype.md]\ntab_size = 2\n[filetype.Makefile]\nscroll_dist = 1",
+        )
+        .unwrap();
+
+        assert_eq!(config.editor_for(Some(Path::new("a/b.md"))).tab_size, 2);
+        assert_eq!(config.editor_for(Some(Path::new("a/b.rs"))).tab_size, 8);
+        assert_eq!(config.editor_for(None).tab_size, 8);
+
+        let makefile = config.editor_for(Some(Path::new("Makefile")));
+        assert_eq!((makefile.tab_size, makefile.scroll_dist), (8, 1));
+    }
+
+    #[test]
+    fn validation() {
+        let parse = |text| toml::from_str::<Config>(text).unwrap().validate();
+
+        assert!(parse("").is_ok());
+        assert!(parse("[editor]\ntab_size = 0").is_err());
+        assert!(parse("[editor]\nscroll_dist = 0").is_err());
+        assert!(parse("[editor]\nscroll_grace = 0").is_err());
+        assert!(parse("[filetype.md]\nscroll_grace = 1000").is_err());
+
+        let error = parse("[filetype.go]\ntab_size = 40").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "filetype.go.tab_size must be between 1 and 16"
+        );
+    }
+}
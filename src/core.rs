@@ -1,21 +1,62 @@
 use crate::{
+    args::Args,
     component::frame::Frame,
+    config, keymap,
     message::Message,
     utils::{
+        dirs,
         input::InputReader,
-        out::{Bounds, Out},
+        log::{self, Level},
+        out::{Bounds, Out, Terminal},
+        shared,
     },
 };
 use crossterm::{
-    cursor::{Hide, MoveTo},
-    event::{DisableMouseCapture, EnableMouseCapture},
+    cursor::{Hide, MoveTo, SetCursorStyle, Show},
+    event::{
+        DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     queue,
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::io::{self, Write};
+use std::{
+    backtrace::Backtrace,
+    env,
+    fs::{self, OpenOptions},
+    io::{self, BufWriter, Write},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    thread::{self, ThreadId},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 pub type Res<T = ()> = anyhow::Result<T>;
 
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+static PANIC_REPORT: Mutex<Option<String>> = Mutex::new(None);
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+static MAIN_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+pub fn load_settings() -> Vec<String> {
+    let mut errors = vec![];
+    match config::load() {
+        Ok(config) => shared::set(|shared| shared.config = config),
+        Err(error) => errors.push(format!("{error:#}")),
+    }
+    match keymap::load() {
+        Ok(keymap) => shared::set(|shared| shared.keymap = keymap),
+        Err(error) => errors.push(format!("{error:#}")),
+    }
+
+    errors
+}
+
 #[derive(Debug)]
 pub struct Core {
     frame: Frame,
@@ -23,7 +64,7 @@ pub struct Core {
 }
 
 impl Core {
-    pub fn new() -> Res<Self> {
+    pub fn new(args: Args) -> Res<Self> {
         let (width, height) = terminal::size()?;
         let bounds = Bounds {
             x0: 0,
@@ -31,12 +72,35 @@ impl Core {
             x1: width,
             y1: height,
         };
+        let load_errors = load_settings();
+
+        let mut frame = Frame::new(bounds, &args)?;
+        for error in load_errors {
+            frame.update(&Message::notify(Level::Error, error))?;
+        }
 
+        let _ = MAIN_THREAD.set(thread::current().id());
+        install_panic_hook();
         terminal::enable_raw_mode()?;
 
-        let mut out = io::stdout().lock();
+        let mut out = BufWriter::new(Terminal::open()?);
         let init_result: Res<_> = (|| {
-            queue!(out, EnterAlternateScreen, EnableMouseCapture)?;
+            queue!(
+                out,
+                EnterAlternateScreen,
+                EnableMouseCapture,
+                EnableFocusChange
+            )?;
+            if terminal::supports_keyboard_enhancement().unwrap_or(false) {
+                queue!(
+                    out,
+                    PushKeyboardEnhancementFlags(
+                        KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    )
+                )?;
+                KEYBOARD_ENHANCED.store(true, Ordering::Relaxed);
+                log::debug!("keyboard enhancement enabled");
+            }
             out.flush()?;
             Ok(())
         })();
@@ -45,19 +109,19 @@ impl Core {
             terminal::disable_raw_mode()?;
             Err(error.into())
         } else {
-            Ok(Self {
-                frame: Frame::new(bounds)?,
-                out,
-            })
+            Ok(Self { frame, out })
         }
     }
 
-    pub fn run(mut self) -> Res<Self> {
+    pub fn run(&mut self) -> Res {
         let input_reader = InputReader::new();
         let mut updated = true;
+        let mut last_tick = Instant::now();
 
         'runtime: loop {
-            for event in input_reader.read()? {
+            let until_tick = TICK_INTERVAL.saturating_sub(last_tick.elapsed());
+
+            for event in input_reader.read(until_tick)? {
                 if let Ok(input) = event.try_into() {
                     updated = true;
 
@@ -76,11 +140,16 @@ impl Core {
                     }
 
                     if quit {
-                        break 'runtime Ok(self);
+                        break 'runtime Ok(());
                     }
                 }
             }
 
+            if last_tick.elapsed() >= TICK_INTERVAL {
+                last_tick = Instant::now();
+                updated |= self.frame.tick()?;
+            }
+
             if updated {
                 queue!(self.out, MoveTo(0, 0), Hide)?;
                 self.frame.view(&mut self.out)?;
@@ -90,15 +159,105 @@ impl Core {
             updated = false;
         }
     }
+
+    pub fn piped(&self) -> Res<Option<Vec<u8>>> {
+        self.frame
+            .portals()
+            .find(|portal| portal.is_stdin())
+            .map(|portal| {
+                let mut piped = vec![];
+                portal.write_to(&mut piped)?;
+                Ok(piped)
+            })
+            .transpose()
+    }
+
+    pub fn recover(&self) -> Vec<PathBuf> {
+        let dir = dirs::state_dir()
+            .unwrap_or_else(|| env::temp_dir().join("neonano"))
+            .join("recovery");
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        self.frame
+            .contents()
+            .filter_map(|content| Some((content, content.recoverable()?)))
+            .enumerate()
+            .filter_map(|(i, (content, name))| {
+                let path = dir.join(format!("{stamp}-{i}-{}", name.replace(['/', '\\'], "%")));
+                let written = panic::catch_unwind(AssertUnwindSafe(|| -> Res {
+                    fs::create_dir_all(&dir)?;
+                    content.write_to(fs::File::create(&path)?)
+                }));
+
+                match written {
+                    Ok(Ok(())) => {
+                        log::warning!("recovered {name} to {}", path.display());
+                        Some(path)
+                    }
+                    Ok(Err(error)) => {
+                        log::error!("failed to recover {name}: {error:#}");
+                        None
+                    }
+                    Err(_) => None,
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn take_panic_report() -> Option<String> {
+    PANIC_REPORT.lock().ok()?.take()
+}
+
+fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let current = thread::current();
+        let report = format!("{info}\n\n{}", Backtrace::force_capture());
+
+        if MAIN_THREAD.get() != Some(&current.id()) {
+            let name = current.name().unwrap_or("background");
+            log::error!("panic in {name} thread: {report}");
+            return;
+        }
+
+        let _ = match OpenOptions::new().write(true).open("/dev/tty") {
+            Ok(mut tty) => restore_terminal(&mut tty),
+            Err(_) => restore_terminal(&mut io::stderr()),
+        };
+
+        log::error!("{report}");
+        if let Ok(mut panic_report) = PANIC_REPORT.lock() {
+            panic_report.get_or_insert(report);
+        }
+    }));
+}
+
+fn restore_terminal(out: &mut impl Write) -> Res {
+    let queued = (|| -> Res<_> {
+        if KEYBOARD_ENHANCED.swap(false, Ordering::Relaxed) {
+            queue!(out, PopKeyboardEnhancementFlags)?;
+        }
+        queue!(
+            out,
+            DisableFocusChange,
+            DisableMouseCapture,
+            LeaveAlternateScreen,
+            SetCursorStyle::DefaultUserShape,
+            Show
+        )?;
+        out.flush()?;
+        Ok(())
+    })();
+    terminal::disable_raw_mode()?;
+
+    queued
 }
 
 impl Drop for Core {
     fn drop(&mut self) {
-        let _ = (|| -> Res<_> {
-            queue!(self.out, DisableMouseCapture, LeaveAlternateScreen)?;
-            self.out.flush()?;
-            Ok(())
-        })();
-        terminal::disable_raw_mode().unwrap();
+        let _ = restore_terminal(&mut self.out);
     }
 }
@@ -0,0 +1,772 @@
+use crate::{
+    command::Command,
+    core::Res,
+    message::{self, Key, KeyCombo},
+    utils::dirs,
+};
+use anyhow::{anyhow, Context as _};
+use crossterm::event::MediaKeyCode;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    io::ErrorKind,
+    path::PathBuf,
+    str::FromStr,
+};
+
+const UNBOUND: &str = "none";
+const MAX_FUNCTION_KEY: u8 = 24;
+
+const KEY_NAMES: &[(Key, &str)] = &[
+    (Key::Char(' '), "space"),
+    (Key::Backspace, "backspace"),
+    (Key::Enter, "enter"),
+    (Key::Left, "left"),
+    (Key::Right, "right"),
+    (Key::Up, "up"),
+    (Key::Down, "down"),
+    (Key::Home, "home"),
+    (Key::End, "end"),
+    (Key::PageUp, "pageup"),
+    (Key::PageDown, "pagedown"),
+    (Key::Tab, "tab"),
+    (Key::Delete, "delete"),
+    (Key::Insert, "insert"),
+    (Key::Esc, "esc"),
+    (Key::CapsLock, "capslock"),
+    (Key::ScrollLock, "scrolllock"),
+    (Key::NumLock, "numlock"),
+    (Key::PrintScreen, "printscreen"),
+    (Key::Pause, "pause"),
+    (Key::Menu, "menu"),
+    (Key::KeypadBegin, "keypadbegin"),
+    (Key::Media(MediaKeyCode::Play), "mediaplay"),
+    (Key::Media(MediaKeyCode::Pause), "mediapause"),
+    (Key::Media(MediaKeyCode::PlayPause), "mediaplaypause"),
+    (Key::Media(MediaKeyCode::Reverse), "mediareverse"),
+    (Key::Media(MediaKeyCode::Stop), "mediastop"),
+    (Key::Media(MediaKeyCode::FastForward), "mediafastforward"),
+    (Key::Media(MediaKeyCode::Rewind), "mediarewind"),
+    (Key::Media(MediaKeyCode::TrackNext), "medianext"),
+    (Key::Media(MediaKeyCode::TrackPrevious), "mediaprevious"),
+    (Key::Media(MediaKeyCode::Record), "mediarecord"),
+    (Key::Media(MediaKeyCode::LowerVolume), "volumedown"),
+    (Key::Media(MediaKeyCode::RaiseVolume), "volumeup"),
+    (Key::Media(MediaKeyCode::MuteVolume), "volumemute"),
+];
+
+const DEFAULTS: &[(Context, &str, Command)] = &[
+    (Context::Global, "ctrl+c", Command::Quit),
+    (Context::Global, "ctrl+x", Command::Quit),
+    (Context::Global, "ctrl+n", Command::Messages),
+    (Context::Global, "ctrl+shift+p", Command::Palette),
+    (Context::Global, "f1", Command::Palette),
+    (Context::Global, "ctrl+pagedown", Command::TileNext),
+    (Context::Global, "ctrl+pageup", Command::TilePrev),
+    (Context::Global, "ctrl+l", Command::TileLog),
+    (Context::Global, "f8", Command::TileSplit),
+    (Context::Global, "f9", Command::TileFocus),
+    (Context::Global, "f5", Command::MacroRecord),
+    (Context::Global, "f6", Command::MacroStop),
+    (Context::Global, "f7", Command::MacroPlay),
+    (Context::Editor, "esc", Command::Close),
+    (Context::Editor, "ctrl+s", Command::Save),
+    (Context::Editor, "ctrl+shift+s", Command::SaveAs),
+    (Context::Editor, "ctrl+g", Command::Goto),
+    (Context::Editor, "ctrl+f", Command::SearchStart),
+    (Context::Editor, "f3", Command::SearchNext),
+    (Context::Editor, "ctrl+e", Command::CycleEnding),
+    (Context::Editor, "ctrl+t", Command::Follow),
+    (Context::Editor, "ctrl+r", Command::ReadOnly),
+    (Context::Editor, "up", Command::CursorUp),
+    (Context::Editor, "down", Command::CursorDown),
+    (Context::Editor, "left", Command::CursorLeft),
+    (Context::Editor, "right", Command::CursorRight),
+    (Context::Editor, "ctrl+left", Command::WordLeft),
+    (Context::Editor, "ctrl+right", Command::WordRight),
+    (Context::Editor, "home", Command::LineStart),
+    (Context::Editor, "end", Command::LineEnd),
+    (Context::Editor, "ctrl+home", Command::Top),
+    (Context::Editor, "ctrl+end", Command::Bottom),
+    (Context::Editor, "pageup", Command::PageUp),
+    (Context::Editor, "pagedown", Command::PageDown),
+    (Context::Editor, "enter", Command::Newline),
+    (Context::Editor, "ctrl+shift+enter", Command::OpenAbove),
+    (Context::Editor, "ctrl+enter", Command::OpenBelow),
+    (Context::Editor, "tab", Command::Tab),
+    (Context::Editor, "backspace", Command::Backspace),
+    (Context::Editor, "ctrl+backspace", Command::BackspaceWord),
+    (Context::Editor, "delete", Command::Delete),
+    (Context::Editor, "ctrl+delete", Command::DeleteWord),
+    (Context::Pager, "space", Command::PageDown),
+    (Context::Pager, "b", Command::PageUp),
+    (Context::Pager, "g", Command::Top),
+    (Context::Pager, "G", Command::Bottom),
+    (Context::Pager, "/", Command::SearchStart),
+    (Context::Pager, "n", Command::SearchNext),
+    (Context::Pager, "q", Command::Quit),
+    (Context::Normal, "esc", Command::NormalMode),
+    (Context::Normal, "enter", Command::CursorDown),
+    (Context::Normal, "backspace", Command::CursorLeft),
+    (Context::Insert, "esc", Command::NormalMode),
+    (Context::FilePicker, "up", Command::CursorUp),
+    (Context::FilePicker, "down", Command::CursorDown),
+    (Context::FilePicker, "enter", Command::PickerOpen),
+    (Context::FilePicker, "esc", Command::PickerBack),
+    (Context::Viewer, "esc", Command::Close),
+    (Context::Viewer, "ctrl+s", Command::Save),
+    (Context::Viewer, "ctrl+f", Command::SearchStart),
+    (Context::Viewer, "f3", Command::SearchNext),
+    (Context::Viewer, "ctrl+t", Command::Follow),
+    (Context::Viewer, "tab", Command::Tab),
+    (Context::Viewer, "up", Command::CursorUp),
+    (Context::Viewer, "down", Command::CursorDown),
+    (Context::Viewer, "left", Command::CursorLeft),
+    (Context::Viewer, "right", Command::CursorRight),
+    (Context::Viewer, "home", Command::LineStart),
+    (Context::Viewer, "end", Command::LineEnd),
+    (Context::Viewer, "ctrl+home", Command::Top),
+    (Context::Viewer, "ctrl+end", Command::Bottom),
+    (Context::Viewer, "pageup", Command::PageUp),
+    (Context::Viewer, "pagedown", Command::PageDown),
+];
+
+const EMACS: &[(Context, &str, Command)] = &[
+    (Context::Global, "alt+x", Command::Palette),
+    (Context::Global, "ctrl+x ctrl+c", Command::Quit),
+    (Context::Global, "ctrl+x o", Command::TileNext),
+    (Context::Global, "ctrl+x 2", Command::TileSplit),
+    (Context::Editor, "ctrl+x ctrl+s", Command::Save),
+    (Context::Editor, "ctrl+x ctrl+w", Command::SaveAs),
+    (Context::Editor, "ctrl+x ctrl+q", Command::ReadOnly),
+    (Context::Editor, "ctrl+x k", Command::Close),
+    (Context::Editor, "alt+g g", Command::Goto),
+    (Context::Editor, "ctrl+s", Command::SearchStart),
+    (Context::Editor, "ctrl+a", Command::LineStart),
+    (Context::Editor, "ctrl+e", Command::LineEnd),
+    (Context::Editor, "ctrl+f", Command::CursorRight),
+    (Context::Editor, "ctrl+b", Command::CursorLeft),
+    (Context::Editor, "ctrl+n", Command::CursorDown),
+    (Context::Editor, "ctrl+p", Command::CursorUp),
+    (Context::Editor, "alt+f", Command::WordRight),
+    (Context::Editor, "alt+b", Command::WordLeft),
+    (Context::Editor, "alt+<", Command::Top),
+    (Context::Editor, "alt+>", Command::Bottom),
+    (Context::Editor, "ctrl+v", Command::PageDown),
+    (Context::Editor, "alt+v", Command::PageUp),
+    (Context::Editor, "ctrl+d", Command::Delete),
+    (Context::Editor, "alt+d", Command::DeleteWord),
+    (Context::Editor, "alt+backspace", Command::BackspaceWord),
+    (Context::Editor, "ctrl+space", Command::SetMark),
+    (Context::Editor, "ctrl+g", Command::ClearMark),
+    (Context::Editor, "ctrl+w", Command::KillRegion),
+    (Context::Editor, "alt+w", Command::CopyRegion),
+    (Context::Editor, "ctrl+k", Command::KillLine),
+    (Context::Editor, "ctrl+y", Command::Yank),
+    (Context::Editor, "alt+y", Command::YankPop),
+];
+
+// Emacs reserves C-c as a prefix for user bindings, so it must not quit.
+const EMACS_UNBOUND: &[(Context, &str)] = &[(Context::Global, "ctrl+c")];
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Context {
+    Global,
+    Editor,
+    Pager,
+    Normal,
+    Insert,
+    FilePicker,
+    Viewer,
+    Prompt,
+}
+
+impl Context {
+    fn parent(self) -> Option<Self> {
+        match self {
+            Self::Global => None,
+            Self::Pager | Self::Normal | Self::Insert => Some(Self::Editor),
+            Self::Editor | Self::FilePicker | Self::Viewer | Self::Prompt => Some(Self::Global),
+        }
+    }
+}
+
+impl FromStr for Context {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Res<Self> {
+        Ok(match name {
+            "global" => Self::Global,
+            "editor" => Self::Editor,
+            "pager" => Self::Pager,
+            "normal" => Self::Normal,
+            "insert" => Self::Insert,
+            "filepicker" => Self::FilePicker,
+            "viewer" => Self::Viewer,
+            "prompt" => Self::Prompt,
+            _ => return Err(anyhow!("unknown context '{name}'")),
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Preset {
+    Default,
+    Emacs,
+}
+
+impl FromStr for Preset {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Res<Self> {
+        Ok(match name {
+            "default" => Self::Default,
+            "emacs" => Self::Emacs,
+            _ => return Err(anyhow!("unknown preset '{name}'")),
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Resolution {
+    Command(Command),
+    Pending,
+    Unbound,
+}
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: HashMap<(Context, Vec<KeyCombo>), Option<Command>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULTS
+            .iter()
+            .map(|&(context, keys, command)| {
+                let keys = parse_sequence(keys).expect("default bindings are valid");
+                ((context, keys), Some(command))
+            })
+            .collect();
+
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn resolve(&self, context: Context, keys: &[KeyCombo]) -> Resolution {
+        let keys: Vec<_> = keys.iter().copied().map(normalize).collect();
+        let mut context = Some(context);
+
+        while let Some(current) = context {
+            let bound = self.bindings.get(&(current, keys.clone()));
+            if let Some(&Some(command)) = bound {
+                return Resolution::Command(command);
+            }
+
+            let prefix = self.bindings.iter().any(|((bound, sequence), command)| {
+                *bound == current
+                    && command.is_some()
+                    && sequence.len() > keys.len()
+                    && sequence.starts_with(&keys)
+            });
+            if prefix {
+                return Resolution::Pending;
+            }
+            // Unbinding a sequence in a context also hides the parent's binding for it.
+            if bound.is_some() {
+                return Resolution::Unbound;
+            }
+
+            context = current.parent();
+        }
+
+        Resolution::Unbound
+    }
+
+    pub fn binding(&self, context: Context, command: Command) -> Option<&[KeyCombo]> {
+        let mut context = Some(context);
+
+        while let Some(current) = context {
+            let shortest = self
+                .bindings
+                .iter()
+                .filter(|((bound, _), bound_command)| {
+                    *bound == current && **bound_command == Some(command)
+                })
+                .map(|((_, sequence), _)| sequence.as_slice())
+                .filter(|&sequence| self.resolve(current, sequence) == Resolution::Command(command))
+                .min_by_key(|sequence| (sequence.len(), display_sequence(sequence)));
+
+            if shortest.is_some() {
+                return shortest;
+            }
+            context = current.parent();
+        }
+
+        None
+    }
+
+    fn apply_preset(&mut self, preset: Preset) {
+        let (bindings, unbound) = match preset {
+            Preset::Default => return,
+            Preset::Emacs => (EMACS, EMACS_UNBOUND),
+        };
+
+        for &(context, keys) in unbound {
+            let keys = parse_sequence(keys).expect("preset bindings are valid");
+            self.bindings.insert((context, keys), None);
+        }
+
+        for &(context, keys, command) in bindings {
+            let keys = parse_sequence(keys).expect("preset bindings are valid");
+
+            for len in 1..keys.len() {
+                if let Some(shadowed) = self.bindings.get_mut(&(context, keys[..len].to_vec())) {
+                    *shadowed = None;
+                }
+            }
+            self.bindings.insert((context, keys), Some(command));
+        }
+    }
+
+    fn apply(&mut self, text: &str) -> Res {
+        let mut table: toml::Table =
+            toml::from_str(text).map_err(|error| anyhow!("{}", error.message().trim_end()))?;
+
+        if let Some(preset) = table.remove("preset") {
+            let preset = preset.as_str().context("preset must be a string")?;
+            self.apply_preset(preset.parse()?);
+        }
+
+        let contexts: BTreeMap<String, BTreeMap<String, String>> = table
+            .try_into()
+            .map_err(|error: toml::de::Error| anyhow!("{}", error.message().trim_end()))?;
+
+        for (context, bindings) in contexts {
+            let context = context.parse()?;
+
+            for (keys, command) in bindings {
+                let command = match command.as_str() {
+                    UNBOUND => None,
+                    name => Some(name.parse().with_context(|| format!("binding '{keys}'"))?),
+                };
+
+                self.bindings
+                    .insert((context, parse_sequence(&keys)?), command);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn normalize(combo: KeyCombo) -> KeyCombo {
+    match combo.key {
+        Key::Char(c) => KeyCombo {
+            key: Key::Char(if combo.shift { message::shifted(c) } else { c }),
+            shift: false,
+            ..combo
+        },
+        _ => combo,
+    }
+}
+
+pub fn parse_sequence(text: &str) -> Res<Vec<KeyCombo>> {
+    let keys = text
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Res<Vec<_>>>()?;
+
+    match keys.is_empty() {
+        true => Err(anyhow!("empty key sequence")),
+        false => Ok(keys),
+    }
+}
+
+pub fn display_sequence(keys: &[KeyCombo]) -> String {
+    keys.iter()
+        .map(KeyCombo::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl FromStr for KeyCombo {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Res<Self> {
+        let (modifiers, key) = match text.strip_suffix('+') {
+            Some(modifiers) if modifiers.is_empty() || modifiers.ends_with('+') => (modifiers, "+"),
+            _ => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+
+        let mut combo = KeyCombo {
+            key: Key::Esc,
+            shift: false,
+            ctrl: false,
+            alt: false,
+            super_: false,
+        };
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "shift" => combo.shift = true,
+                "ctrl" => combo.ctrl = true,
+                "alt" | "meta" => combo.alt = true,
+                "super" => combo.super_ = true,
+                _ => return Err(anyhow!("unknown modifier '{modifier}' in '{text}'")),
+            }
+        }
+
+        let mut chars = key.chars();
+        combo.key = match (chars.next(), chars.next()) {
+            (Some(c), None) if combo.shift => Key::Char(message::shifted(c)),
+            (Some(c), None) => Key::Char(c),
+            _ => {
+                let name = key.to_ascii_lowercase();
+                let function = name
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| (1..=MAX_FUNCTION_KEY).contains(n));
+
+                match function {
+                    Some(n) => Key::F(n),
+                    None => KEY_NAMES
+                        .iter()
+                        .find(|&&(_, known)| known == name)
+                        .map(|&(key, _)| key)
+                        .ok_or_else(|| anyhow!("unknown key '{key}' in '{text}'"))?,
+                }
+            }
+        };
+
+        Ok(normalize(combo))
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.ctrl, "ctrl+"),
+            (self.alt, "alt+"),
+            (self.super_, "super+"),
+            (self.shift, "shift+"),
+        ];
+        for (_, modifier) in modifiers.iter().filter(|(held, _)| *held) {
+            f.write_str(modifier)?;
+        }
+
+        match self.key {
+            Key::Char(' ') => f.write_str("space"),
+            Key::Char(c) => write!(f, "{c}"),
+            Key::F(n) => write!(f, "f{n}"),
+            key => {
+                let name = KEY_NAMES
+                    .iter()
+                    .find(|&&(known, _)| known == key)
+                    .map_or("?", |&(_, name)| name);
+
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("keymap.toml"))
+}
+
+pub fn load() -> Res<Keymap> {
+    let mut keymap = Keymap::default();
+    let Some(path) = path() else {
+        return Ok(keymap);
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            keymap
+                .apply(&text)
+                .map_err(|error| anyhow!("invalid keymap {}: {error:#}", path.display()))?;
+
+            Ok(keymap)
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(keymap),
+        Err(error) => {
+            Err(error).with_context(|| format!("failed to read keymap {}", path.display()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combo(text: &str) -> KeyCombo {
+        text.parse().unwrap()
+    }
+
+    fn resolve(keymap: &Keymap, context: Context, keys: &str) -> Resolution {
+        keymap.resolve(context, &parse_sequence(keys).unwrap())
+    }
+
+    #[test]
+    fn parses_combos() {
+        assert_eq!(
+            combo("ctrl+s"),
+            KeyCombo {
+                key: Key::Char('s'),
+                shift: false,
+                ctrl: true,
+                alt: false,
+                super_: false,
+            }
+        );
+        assert_eq!(combo("Ctrl+Shift+p").key, Key::Char('P'));
+        assert_eq!(combo("ctrl+shift+ä").key, Key::Char('Ä'));
+        assert_eq!(combo("shift+ß").key, Key::Char('ß'));
+        assert_eq!(combo("ctrl++").key, Key::Char('+'));
+        assert_eq!(combo("+").key, Key::Char('+'));
+        assert!(combo("shift+pagedown").shift);
+        assert!("hyper+a".parse::<KeyCombo>().is_err());
+        assert!("ctrl+nope".parse::<KeyCombo>().is_err());
+        assert!("f25".parse::<KeyCombo>().is_err());
+    }
+
+    #[test]
+    fn parses_extended_keys() {
+        let combo = combo("alt+super+F12");
+
+        assert_eq!(combo.key, Key::F(12));
+        assert!(combo.alt && combo.super_ && !combo.ctrl);
+        assert!(self::combo("meta+x").alt);
+        assert_eq!(
+            self::combo("volumeup").key,
+            Key::Media(MediaKeyCode::RaiseVolume)
+        );
+    }
+
+    #[test]
+    fn displays_combos() {
+        for text in [
+            "ctrl+s",
+            "ctrl+alt+f5",
+            "super+space",
+            "shift+tab",
+            "G",
+            "medianext",
+        ] {
+            assert_eq!(combo(text).to_string(), text);
+        }
+        assert_eq!(combo("shift+ctrl+k").to_string(), "ctrl+K");
+    }
+
+    #[test]
+    fn resolves_through_parents() {
+        let keymap = Keymap::default();
+
+        assert_eq!(
+            resolve(&keymap, Context::Pager, "up"),
+            Resolution::Command(Command::CursorUp)
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Prompt, "ctrl+c"),
+            Resolution::Command(Command::Quit)
+        );
+        assert_eq!(resolve(&keymap, Context::Prompt, "up"), Resolution::Unbound);
+    }
+
+    #[test]
+    fn matches_shifted_non_ascii() {
+        let mut keymap = Keymap::default();
+        keymap
+            .apply("[editor]\n\"ctrl+shift+ä\" = \"buffer.save\"")
+            .unwrap();
+        let pressed = KeyCombo {
+            key: Key::Char('ä'),
+            shift: true,
+            ctrl: true,
+            alt: false,
+            super_: false,
+        };
+
+        assert_eq!(
+            keymap.resolve(Context::Editor, &[pressed]),
+            Resolution::Command(Command::Save)
+        );
+    }
+
+    #[test]
+    fn viewer_commands() {
+        let keymap = Keymap::default();
+
+        assert_eq!(
+            resolve(&keymap, Context::Viewer, "ctrl+s"),
+            Resolution::Command(Command::Save)
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Viewer, "ctrl+end"),
+            Resolution::Command(Command::Bottom)
+        );
+        assert_eq!(resolve(&keymap, Context::Viewer, "a"), Resolution::Unbound);
+    }
+
+    #[test]
+    fn modal_contexts_override_editor() {
+        let keymap = Keymap::default();
+
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "esc"),
+            Resolution::Command(Command::Close)
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Insert, "esc"),
+            Resolution::Command(Command::NormalMode)
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Normal, "ctrl+s"),
+            Resolution::Command(Command::Save)
+        );
+        assert_eq!(resolve(&keymap, Context::Normal, "d"), Resolution::Unbound);
+    }
+
+    #[test]
+    fn shifted_chars_match_their_case() {
+        let keymap = Keymap::default();
+        let typed = KeyCombo {
+            key: Key::Char('G'),
+            shift: true,
+            ctrl: false,
+            alt: false,
+            super_: false,
+        };
+
+        assert_eq!(
+            keymap.resolve(Context::Pager, &[typed]),
+            Resolution::Command(Command::Bottom)
+        );
+    }
+
+    #[test]
+    fn overrides_and_unbinds() {
+        let mut keymap = Keymap::default();
+        keymap
+            .apply("[global]\n\"ctrl+c\" = \"none\"\n\"ctrl+q\" = \"app.quit\"")
+            .unwrap();
+
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+c"),
+            Resolution::Unbound
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+q"),
+            Resolution::Command(Command::Quit)
+        );
+    }
+
+    #[test]
+    fn resolves_sequences() {
+        let mut keymap = Keymap::default();
+        keymap
+            .apply("[editor]\n\"ctrl+k ctrl+c\" = \"buffer.save\"")
+            .unwrap();
+
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+k"),
+            Resolution::Pending
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Pager, "ctrl+k ctrl+c"),
+            Resolution::Command(Command::Save)
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+k x"),
+            Resolution::Unbound
+        );
+        assert_eq!(
+            resolve(&keymap, Context::FilePicker, "ctrl+k"),
+            Resolution::Unbound
+        );
+        assert_eq!(
+            display_sequence(&parse_sequence("ctrl+k  ctrl+c").unwrap()),
+            "ctrl+k ctrl+c"
+        );
+    }
+
+    #[test]
+    fn emacs_preset() {
+        let mut keymap = Keymap::default();
+        keymap
+            .apply("preset = \"emacs\"\n[editor]\n\"ctrl+y\" = \"none\"")
+            .unwrap();
+
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+a"),
+            Resolution::Command(Command::LineStart)
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+x"),
+            Resolution::Pending
+        );
+        assert_eq!(
+            resolve(&keymap, Context::FilePicker, "ctrl+x ctrl+c"),
+            Resolution::Command(Command::Quit)
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+y"),
+            Resolution::Unbound
+        );
+        assert_eq!(
+            resolve(&keymap, Context::FilePicker, "ctrl+x"),
+            Resolution::Pending
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+c"),
+            Resolution::Unbound
+        );
+
+        keymap
+            .apply("[global]\n\"ctrl+c m\" = \"app.messages\"")
+            .unwrap();
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+c"),
+            Resolution::Pending
+        );
+        assert_eq!(
+            resolve(&keymap, Context::Editor, "ctrl+c m"),
+            Resolution::Command(Command::Messages)
+        );
+        assert!(keymap.apply("preset = \"vscode\"").is_err());
+    }
+
+    #[test]
+    fn finds_bindings() {
+        let mut keymap = Keymap::default();
+        keymap.apply("[global]\n\"ctrl+c\" = \"none\"").unwrap();
+        let binding = |context, command| keymap.binding(context, command).map(display_sequence);
+
+        assert_eq!(
+            binding(Context::Editor, Command::Save).as_deref(),
+            Some("ctrl+s")
+        );
+        assert_eq!(
+            binding(Context::Pager, Command::Bottom).as_deref(),
+            Some("G")
+        );
+        assert_eq!(
+            binding(Context::Editor, Command::Quit).as_deref(),
+            Some("ctrl+x")
+        );
+        assert_eq!(binding(Context::FilePicker, Command::Save), None);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let mut keymap = Keymap::default();
+
+        assert!(keymap.apply("[editor]\n\" \" = \"app.quit\"").is_err());
+        assert!(keymap.apply("[nowhere]\nx = \"app.quit\"").is_err());
+        assert!(keymap.apply("[editor]\nx = \"app.explode\"").is_err());
+        assert!(keymap
+            .apply("[editor]\n\"hyper+x\" = \"app.quit\"")
+            .is_err());
+    }
+}
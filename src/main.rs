@@ -1,18 +1,103 @@
+mod args;
+mod command;
 mod component;
+mod config;
 mod core;
+mod keymap;
 mod message;
 mod utils;
+mod vi;
 
+use args::Action;
 use core::Core;
-use std::fs::write;
-
-fn main() {
-    write(
-        "debug.txt",
-        match Core::new().and_then(Core::run) {
-            Ok(core) => format!("{core:#?}"),
-            Err(error) => format!("{error:?}"),
-        },
-    )
-    .unwrap()
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use utils::log::{self, Level};
+
+const EXIT_USAGE: u8 = 2;
+const EXIT_PANIC: u8 = 101;
+
+fn main() -> ExitCode {
+    let args = match args::parse(env::args().skip(1)) {
+        Ok(Action::Edit(args)) => args,
+        Ok(Action::Help) => {
+            print!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Action::Version) => {
+            println!("neonano {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprint!("neonano: {error}\n\n{}", args::USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let log_file = args.log_file.clone().or_else(log::default_path);
+    let log_level = args.log_level.unwrap_or(Level::Info);
+    if let Err(error) = log::init(log_file.as_deref(), log_level) {
+        eprintln!("neonano: {error:#}");
+        let _ = log::init(None, log_level);
+    }
+    log::info!("neonano {} starting", env!("CARGO_PKG_VERSION"));
+
+    let mut core = match Core::new(args) {
+        Ok(core) => core,
+        Err(error) => return fail(error),
+    };
+
+    let result = match panic::catch_unwind(AssertUnwindSafe(|| core.run())) {
+        Ok(result) => result.and_then(|()| core.piped()),
+        Err(_) => {
+            let recovered = core.recover();
+            drop(core);
+            crash_report(&recovered, log_file.as_deref());
+
+            return ExitCode::from(EXIT_PANIC);
+        }
+    };
+    drop(core);
+
+    let result = result.and_then(|piped| match piped {
+        Some(piped) if !io::stdout().is_terminal() => Ok(io::stdout().write_all(&piped)?),
+        _ => Ok(()),
+    });
+
+    match result {
+        Ok(()) => {
+            log::info!("neonano exiting");
+            ExitCode::SUCCESS
+        }
+        Err(error) => fail(error),
+    }
+}
+
+fn fail(error: anyhow::Error) -> ExitCode {
+    log::error!("{error:?}");
+    eprintln!("neonano: {error:#}");
+    ExitCode::FAILURE
+}
+
+fn crash_report(recovered: &[PathBuf], log_file: Option<&Path>) {
+    let report = core::take_panic_report().unwrap_or_else(|| "unknown panic".into());
+    let summary = report.lines().next().unwrap_or_default();
+
+    eprintln!("neonano crashed: {summary}");
+    if recovered.is_empty() {
+        eprintln!("there were no unsaved buffers to recover");
+    } else {
+        eprintln!("unsaved buffers were written to:");
+        for path in recovered {
+            eprintln!("  {}", path.display());
+        }
+    }
+    if let Some(log_file) = log_file {
+        eprintln!("the full report is in {}", log_file.display());
+    }
 }
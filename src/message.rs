@@ -1,15 +1,50 @@
+use crate::{command::Command, utils::log::Level};
 use crossterm::event::{
-    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MediaKeyCode, MouseEvent, MouseEventKind,
 };
 use std::path::PathBuf;
 
 #[derive(Clone, Debug)]
 pub enum Message {
     Input(Input),
+    Command(Command),
     Open(PathBuf),
+    Notify {
+        level: Level,
+        text: String,
+    },
+    Prompt {
+        kind: PromptKind,
+        text: String,
+        complete: Option<Completer>,
+    },
+    PromptResult {
+        kind: PromptKind,
+        text: String,
+    },
     Quit,
 }
 
+pub type Completer = fn(&str) -> Vec<String>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PromptKind {
+    SaveAs,
+    Goto,
+    Search,
+    MacroRecord,
+    MacroPlay,
+}
+
+impl Message {
+    pub fn notify(level: Level, text: impl Into<String>) -> Self {
+        Self::Notify {
+            level,
+            text: text.into(),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Input {
     FocusGained,
@@ -19,14 +54,16 @@ pub enum Input {
     KeyCombo(KeyCombo),
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct KeyCombo {
     pub key: Key,
     pub shift: bool,
     pub ctrl: bool,
+    pub alt: bool,
+    pub super_: bool,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Key {
     Char(char),
     Backspace,
@@ -44,6 +81,23 @@ pub enum Key {
     Insert,
     Esc,
     CapsLock,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Pause,
+    Menu,
+    KeypadBegin,
+    F(u8),
+    Media(MediaKeyCode),
+}
+
+pub fn shifted(c: char) -> char {
+    let mut upper = c.to_uppercase();
+
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => c,
+    }
 }
 
 impl TryFrom<Event> for Input {
@@ -60,9 +114,13 @@ impl TryFrom<Event> for Input {
                 ..
             }) => {
                 if kind == KeyEventKind::Press || kind == KeyEventKind::Repeat {
+                    let shift = modifiers.contains(KeyModifiers::SHIFT);
+
                     Self::KeyCombo(KeyCombo {
                         key: match code {
+                            KeyCode::Char(c) if shift => Key::Char(shifted(c)),
                             KeyCode::Char(c) => Key::Char(c),
+                            KeyCode::Null => Key::Char(' '),
                             KeyCode::Backspace => Key::Backspace,
                             KeyCode::Enter => Key::Enter,
                             KeyCode::Left => Key::Left,
@@ -73,15 +131,25 @@ impl TryFrom<Event> for Input {
                             KeyCode::End => Key::End,
                             KeyCode::PageUp => Key::PageUp,
                             KeyCode::PageDown => Key::PageDown,
-                            KeyCode::Tab => Key::Tab,
+                            KeyCode::Tab | KeyCode::BackTab => Key::Tab,
                             KeyCode::Delete => Key::Delete,
                             KeyCode::Insert => Key::Insert,
                             KeyCode::Esc => Key::Esc,
                             KeyCode::CapsLock => Key::CapsLock,
-                            _ => return Err(()),
+                            KeyCode::ScrollLock => Key::ScrollLock,
+                            KeyCode::NumLock => Key::NumLock,
+                            KeyCode::PrintScreen => Key::PrintScreen,
+                            KeyCode::Pause => Key::Pause,
+                            KeyCode::Menu => Key::Menu,
+                            KeyCode::KeypadBegin => Key::KeypadBegin,
+                            KeyCode::F(n) => Key::F(n),
+                            KeyCode::Media(media) => Key::Media(media),
+                            KeyCode::Modifier(_) => return Err(()),
                         },
-                        shift: modifiers.contains(KeyModifiers::SHIFT),
-                        ctrl: modifiers.contains(KeyModifiers::CONTROL),
+                        shift: shift || code == KeyCode::BackTab,
+                        ctrl: modifiers.contains(KeyModifiers::CONTROL) || code == KeyCode::Null,
+                        alt: modifiers.contains(KeyModifiers::ALT),
+                        super_: modifiers.contains(KeyModifiers::SUPER),
                     })
                 } else {
                     return Err(());
@@ -136,4 +204,38 @@ macro_rules! pressed {
             ..
         }))
     };
+
+    ($key:pat, ctrl + alt) => {
+        Message::Input(crate::message::Input::KeyCombo(crate::message::KeyCombo {
+            key: $key,
+            ctrl: true,
+            alt: true,
+            ..
+        }))
+    };
+
+    ($key:pat, shift + alt) => {
+        Message::Input(crate::message::Input::KeyCombo(crate::message::KeyCombo {
+            key: $key,
+            shift: true,
+            alt: true,
+            ..
+        }))
+    };
+
+    ($key:pat, alt) => {
+        Message::Input(crate::message::Input::KeyCombo(crate::message::KeyCombo {
+            key: $key,
+            alt: true,
+            ..
+        }))
+    };
+
+    ($key:pat, super) => {
+        Message::Input(crate::message::Input::KeyCombo(crate::message::KeyCombo {
+            key: $key,
+            super_: true,
+            ..
+        }))
+    };
 }
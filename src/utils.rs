@@ -1,5 +1,17 @@
+pub mod complete;
+pub mod diff;
+pub mod dirs;
+pub mod editorconfig;
+pub mod follow;
+pub mod fuzzy;
 pub mod input;
+pub mod killring;
 pub mod list;
+pub mod log;
+pub mod macros;
 pub mod out;
 pub mod shared;
 pub mod slotlist;
+pub mod swap;
+pub mod template;
+pub mod text;
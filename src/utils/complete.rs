@@ -0,0 +1,77 @@
+use std::{fs, path::MAIN_SEPARATOR};
+
+pub fn paths(text: &str) -> Vec<String> {
+    let (dir, prefix) = match text.rfind(MAIN_SEPARATOR) {
+        Some(split) => text.split_at(split + 1),
+        None => ("", text),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return vec![];
+    };
+
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let hidden = name.starts_with('.') && !prefix.starts_with('.');
+            if hidden || !name.starts_with(prefix) {
+                return None;
+            }
+
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            Some(match is_dir {
+                true => format!("{dir}{name}{MAIN_SEPARATOR}"),
+                false => format!("{dir}{name}"),
+            })
+        })
+        .collect();
+    paths.sort();
+
+    paths
+}
+
+pub fn common_prefix(candidates: &[String]) -> Option<&str> {
+    let (first, rest) = candidates.split_first()?;
+
+    let len = rest.iter().fold(first.len(), |len, candidate| {
+        first[..len]
+            .char_indices()
+            .zip(candidate.chars())
+            .find(|&((_, a), b)| a != b)
+            .map_or(len.min(candidate.len()), |((i, _), _)| i)
+    });
+
+    Some(&first[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(common_prefix(&[]), None);
+        assert_eq!(common_prefix(&strings(&["src/"])), Some("src/"));
+        assert_eq!(
+            common_prefix(&strings(&["src/main.rs", "src/message.rs", "src/m"])),
+            Some("src/m")
+        );
+        assert_eq!(common_prefix(&strings(&["añb", "añc"])), Some("añ"));
+        assert_eq!(common_prefix(&strings(&["abc", "xyz"])), Some(""));
+    }
+
+    #[test]
+    fn lists_matching_paths() {
+        let paths = paths(&format!(
+            "{}{MAIN_SEPARATOR}Cargo.t",
+            env!("CARGO_MANIFEST_DIR")
+        ));
+
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].ends_with("Cargo.toml"));
+    }
+}
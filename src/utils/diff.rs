@@ -0,0 +1,117 @@
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Change<'text> {
+    Same(&'text str),
+    Removed(&'text str),
+    Added(&'text str),
+}
+
+pub fn lines<'text>(old: &'text str, new: &'text str) -> Vec<Change<'text>> {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_changed = &old[prefix..old.len() - suffix];
+    let new_changed = &new[prefix..new.len() - suffix];
+
+    let mut changes: Vec<_> = old[..prefix]
+        .iter()
+        .map(|&line| Change::Same(line))
+        .collect();
+
+    if (old_changed.len() + 1) * (new_changed.len() + 1) > MAX_TABLE_SIZE {
+        changes.extend(old_changed.iter().map(|&line| Change::Removed(line)));
+        changes.extend(new_changed.iter().map(|&line| Change::Added(line)));
+    } else {
+        changes.extend(common_subsequence(old_changed, new_changed));
+    }
+
+    changes.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|&line| Change::Same(line)),
+    );
+
+    changes
+}
+
+fn common_subsequence<'text>(old: &[&'text str], new: &[&'text str]) -> Vec<Change<'text>> {
+    let width = new.len() + 1;
+    let mut table = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i * width + j] = if old[i] == new[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = vec![];
+
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(Change::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            changes.push(Change::Removed(old[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(new[j]));
+            j += 1;
+        }
+    }
+
+    changes.extend(old[i..].iter().map(|&line| Change::Removed(line)));
+    changes.extend(new[j..].iter().map(|&line| Change::Added(line)));
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical() {
+        assert_eq!(
+            lines("a\nb", "a\nb"),
+            [Change::Same("a"), Change::Same("b")]
+        );
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        assert_eq!(
+            lines("a\nb\nc\nd", "a\nc\nx\nd"),
+            [
+                Change::Same("a"),
+                Change::Removed("b"),
+                Change::Same("c"),
+                Change::Added("x"),
+                Change::Same("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_sides() {
+        assert_eq!(lines("", "a"), [Change::Added("a")]);
+        assert_eq!(lines("a", ""), [Change::Removed("a")]);
+    }
+}
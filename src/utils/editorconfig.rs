@@ -0,0 +1,595 @@
+use crate::{
+    config::MAX_TAB_SIZE,
+    utils::{
+        log,
+        text::{Format, LineEnding},
+    },
+};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use std::{
+    collections::HashSet,
+    fs,
+    io::ErrorKind,
+    path::{self, Path, PathBuf},
+};
+
+const FILE_NAME: &str = ".editorconfig";
+const MAX_GLOB_LEN: usize = 1024;
+const MAX_EXPANSIONS: usize = 1024;
+
+type Class<'pattern> = (bool, Vec<(char, char)>, &'pattern [char]);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum IndentSize {
+    Tab,
+    Columns(usize),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Charset {
+    Latin1,
+    Utf8,
+    Utf8Bom,
+    Utf16Be,
+    Utf16Le,
+}
+
+impl Charset {
+    pub fn encoding(self) -> &'static Encoding {
+        match self {
+            Self::Latin1 => WINDOWS_1252,
+            Self::Utf8 | Self::Utf8Bom => UTF_8,
+            Self::Utf16Be => UTF_16BE,
+            Self::Utf16Le => UTF_16LE,
+        }
+    }
+
+    fn bom(self) -> Option<bool> {
+        match self {
+            Self::Latin1 | Self::Utf8 => Some(false),
+            Self::Utf8Bom => Some(true),
+            Self::Utf16Be | Self::Utf16Le => None,
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Properties {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<IndentSize>,
+    pub tab_width: Option<usize>,
+    pub end_of_line: Option<LineEnding>,
+    pub charset: Option<Charset>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+}
+
+impl Properties {
+    fn set(&mut self, key: &str, value: &str) {
+        let value = value.to_lowercase();
+        let size = || {
+            value
+                .parse()
+                .ok()
+                .filter(|size| (1..=MAX_TAB_SIZE).contains(size))
+        };
+        let flag = || match value.as_str() {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        };
+
+        match key {
+            "indent_style" => {
+                self.indent_style = match value.as_str() {
+                    "tab" => Some(IndentStyle::Tab),
+                    "space" => Some(IndentStyle::Space),
+                    _ => None,
+                }
+            }
+            "indent_size" => {
+                self.indent_size = match value.as_str() {
+                    "tab" => Some(IndentSize::Tab),
+                    _ => size().map(IndentSize::Columns),
+                }
+            }
+            "tab_width" => self.tab_width = size(),
+            "end_of_line" => {
+                self.end_of_line = match value.as_str() {
+                    "lf" => Some(LineEnding::Lf),
+                    "crlf" => Some(LineEnding::CrLf),
+                    "cr" => Some(LineEnding::Cr),
+                    _ => None,
+                }
+            }
+            "charset" => {
+                self.charset = match value.as_str() {
+                    "latin1" => Some(Charset::Latin1),
+                    "utf-8" => Some(Charset::Utf8),
+                    "utf-8-bom" => Some(Charset::Utf8Bom),
+                    "utf-16be" => Some(Charset::Utf16Be),
+                    "utf-16le" => Some(Charset::Utf16Le),
+                    _ => None,
+                }
+            }
+            "trim_trailing_whitespace" => self.trim_trailing_whitespace = flag(),
+            "insert_final_newline" => self.insert_final_newline = flag(),
+            _ => {}
+        }
+    }
+
+    fn indent_width(&self) -> Option<usize> {
+        match self.indent_size? {
+            IndentSize::Tab => self.tab_width,
+            IndentSize::Columns(columns) => Some(columns),
+        }
+    }
+
+    pub fn tab_width(&self) -> Option<usize> {
+        match self.indent_size {
+            Some(IndentSize::Columns(columns)) => self.tab_width.or(Some(columns)),
+            _ => self.tab_width,
+        }
+    }
+
+    pub fn soft_tab(&self) -> Option<usize> {
+        match self.indent_style {
+            Some(IndentStyle::Space) => self.indent_width().or(self.tab_width).or(Some(4)),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, format: &mut Format) {
+        if let Some(ending) = self.end_of_line {
+            format.ending = ending;
+        }
+        if let Some(charset) = self.charset {
+            format.encoding = charset.encoding();
+            format.bom = charset.bom().unwrap_or(format.bom);
+        }
+        if let Some(final_newline) = self.insert_final_newline {
+            format.final_newline = final_newline;
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+struct Section {
+    glob: String,
+    pairs: Vec<(String, String)>,
+}
+
+#[derive(Clone, Default, Debug)]
+struct File {
+    root: bool,
+    sections: Vec<Section>,
+}
+
+fn parse(text: &str) -> File {
+    let mut file = File::default();
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(glob) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            file.sections.push(Section {
+                glob: glob.into(),
+                pairs: vec![],
+            });
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim().to_lowercase(), value.trim());
+
+        match file.sections.last_mut() {
+            Some(section) => section.pairs.push((key, value.into())),
+            None => file.root |= key == "root" && value.eq_ignore_ascii_case("true"),
+        }
+    }
+
+    file
+}
+
+fn closing_brace(pattern: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < pattern.len() {
+        match pattern[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    None
+}
+
+fn alternatives(body: &[char]) -> Vec<&[char]> {
+    let mut parts = vec![];
+    let (mut depth, mut start, mut i) = (0, 0, 0);
+    while i < body.len() {
+        match body[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(&body[start.min(body.len())..]);
+
+    parts
+}
+
+fn range(body: &[char]) -> Option<(i64, i64)> {
+    let body: String = body.iter().collect();
+    let (from, to) = body.split_once("..")?;
+    let (from, to) = (from.parse().ok()?, to.parse().ok()?);
+
+    Some(if from <= to { (from, to) } else { (to, from) })
+}
+
+fn expand(pattern: &[char], from: usize) -> Option<Vec<Vec<char>>> {
+    let mut i = from;
+    while i < pattern.len() {
+        match pattern[i] {
+            '\\' => i += 2,
+            '{' => {
+                let Some(close) = closing_brace(pattern, i) else {
+                    i += 1;
+                    continue;
+                };
+                let body = &pattern[i + 1..close];
+                let parts = alternatives(body);
+                if range(body).is_some() || parts.len() < 2 {
+                    i = close + 1;
+                    continue;
+                }
+
+                let mut patterns = vec![];
+                for part in parts {
+                    let mut expanded = pattern[..i].to_vec();
+                    expanded.extend(part);
+                    expanded.extend(&pattern[close + 1..]);
+
+                    patterns.extend(expand(&expanded, i)?);
+                    if patterns.len() > MAX_EXPANSIONS {
+                        return None;
+                    }
+                }
+
+                return Some(patterns);
+            }
+            _ => i += 1,
+        }
+    }
+
+    Some(vec![pattern.to_vec()])
+}
+
+fn class(pattern: &[char]) -> Option<Class<'_>> {
+    let (negated, pattern) = match pattern.first() {
+        Some('!' | '^') => (true, &pattern[1..]),
+        _ => (false, pattern),
+    };
+    let mut ranges = vec![];
+    let mut i = 0;
+
+    loop {
+        let &c = pattern.get(i)?;
+        if c == ']' && i > 0 {
+            return Some((negated, ranges, &pattern[i + 1..]));
+        }
+        match pattern.get(i + 1..i + 3) {
+            Some(&['-', end]) if end != ']' => {
+                ranges.push((c, end));
+                i += 3;
+            }
+            _ => {
+                ranges.push((c, c));
+                i += 1;
+            }
+        }
+    }
+}
+
+// Patterns and texts are always suffixes of the ones being matched, so their lengths identify a
+// state. Remembering the states that failed keeps `*` and `**` from backtracking exponentially.
+fn matches_at(pattern: &[char], text: &[char], failed: &mut HashSet<(usize, usize)>) -> bool {
+    if failed.contains(&(pattern.len(), text.len())) {
+        return false;
+    }
+    let matched = step(pattern, text, failed);
+    if !matched {
+        failed.insert((pattern.len(), text.len()));
+    }
+
+    matched
+}
+
+fn step(pattern: &[char], text: &[char], failed: &mut HashSet<(usize, usize)>) -> bool {
+    let Some((&first, rest)) = pattern.split_first() else {
+        return text.is_empty();
+    };
+    let literal = |c: char, rest: &[char], failed: &mut HashSet<(usize, usize)>| {
+        text.first() == Some(&c) && matches_at(rest, &text[1..], failed)
+    };
+
+    match first {
+        '*' if rest.first() == Some(&'*') => {
+            (0..=text.len()).any(|skip| matches_at(&rest[1..], &text[skip..], failed))
+        }
+        '*' => (0..=text.len())
+            .take_while(|&skip| skip == 0 || text[skip - 1] != '/')
+            .any(|skip| matches_at(rest, &text[skip..], failed)),
+        '?' => text.first().is_some_and(|&c| c != '/') && matches_at(rest, &text[1..], failed),
+        '[' => match class(rest) {
+            Some((negated, ranges, rest)) => text.first().is_some_and(|&c| {
+                let found = ranges.iter().any(|&(from, to)| (from..=to).contains(&c));
+                c != '/' && found != negated && matches_at(rest, &text[1..], failed)
+            }),
+            None => literal('[', rest, failed),
+        },
+        '{' => {
+            let range = closing_brace(pattern, 0)
+                .and_then(|close| Some((range(&pattern[1..close])?, &pattern[close + 1..])));
+            let Some(((from, to), rest)) = range else {
+                return literal('{', rest, failed);
+            };
+
+            let sign = usize::from(text.first() == Some(&'-'));
+            let digits = text[sign..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            (sign + 1..=sign + digits).any(|end| {
+                let number: String = text[..end].iter().collect();
+                number
+                    .parse()
+                    .is_ok_and(|number: i64| (from..=to).contains(&number))
+                    && matches_at(rest, &text[end..], failed)
+            })
+        }
+        '\\' if !rest.is_empty() => literal(rest[0], &rest[1..], failed),
+        c => literal(c, rest, failed),
+    }
+}
+
+fn matches(glob: &str, path: &str) -> bool {
+    let pattern: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = path.chars().collect();
+    let expanded = match pattern.len() <= MAX_GLOB_LEN {
+        true => expand(&pattern, 0),
+        false => None,
+    };
+    let Some(patterns) = expanded else {
+        log::warning!("ignoring .editorconfig section [{glob}]: the pattern is too complex");
+        return false;
+    };
+
+    patterns
+        .iter()
+        .any(|pattern| matches_at(pattern, &text, &mut HashSet::new()))
+}
+
+fn section_matches(glob: &str, relative: &str) -> bool {
+    match glob.strip_prefix('/') {
+        Some(glob) => matches(glob, relative),
+        None if glob.contains('/') => matches(glob, relative),
+        None => {
+            let name = relative.rsplit('/').next().unwrap_or(relative);
+            matches(glob, name)
+        }
+    }
+}
+
+fn properties(files: &[(PathBuf, File)], path: &Path) -> Properties {
+    let mut properties = Properties::default();
+
+    for (dir, file) in files.iter().rev() {
+        let Ok(relative) = path.strip_prefix(dir) else {
+            continue;
+        };
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        for section in &file.sections {
+            if section_matches(&section.glob, &relative) {
+                for (key, value) in &section.pairs {
+                    properties.set(key, value);
+                }
+            }
+        }
+    }
+
+    properties
+}
+
+pub fn resolve(path: &Path) -> Properties {
+    let Ok(path) = path::absolute(path) else {
+        return Properties::default();
+    };
+    let mut files = vec![];
+
+    for dir in path.ancestors().skip(1) {
+        let config = dir.join(FILE_NAME);
+
+        match fs::read_to_string(&config) {
+            Ok(text) => {
+                let file = parse(&text);
+                let root = file.root;
+                files.push((dir.to_path_buf(), file));
+
+                if root {
+                    break;
+                }
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => log::warning!("ignoring {}: {error}", config.display()),
+        }
+    }
+
+    properties(&files, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("**.rs", "src/main.rs"));
+        assert!(matches("src/**/*.rs", "src/a/b/main.rs"));
+        assert!(matches("?.md", "a.md"));
+        assert!(!matches("?.md", "ab.md"));
+        assert!(matches("*.{js,ts}", "app.ts"));
+        assert!(!matches("*.{js,ts}", "app.rs"));
+        assert!(matches("{src/{a,b},lib}/x", "src/b/x"));
+        assert!(matches("[Mm]akefile", "makefile"));
+        assert!(matches("file[0-9].txt", "file7.txt"));
+        assert!(!matches("file[!0-9].txt", "file7.txt"));
+        assert!(matches("v{1..12}", "v10"));
+        assert!(!matches("v{1..12}", "v13"));
+        assert!(matches("{single}", "{single}"));
+        assert!(matches("a\\*b", "a*b"));
+        assert!(!matches("a\\*b", "axb"));
+    }
+
+    #[test]
+    fn rejects_hostile_globs() {
+        let text = format!("{}b", "a".repeat(64));
+        assert!(!matches(&"*a".repeat(32), &text));
+        assert!(!matches(&"**a".repeat(32), &text));
+        assert!(matches(&"*a".repeat(32), &"a".repeat(64)));
+
+        assert!(!matches(&"{a,b}".repeat(16), &"a".repeat(16)));
+        assert!(matches(&"{a,b}".repeat(8), "abababab"));
+        assert!(!matches(&"?".repeat(MAX_GLOB_LEN + 1), "a"));
+    }
+
+    #[test]
+    fn sections() {
+        assert!(section_matches("*.rs", "src/main.rs"));
+        assert!(section_matches("Makefile", "tools/Makefile"));
+        assert!(section_matches("/src/*.rs", "src/main.rs"));
+        assert!(!section_matches("/src/*.rs", "lib/src/main.rs"));
+        assert!(section_matches("lib/*.rs", "lib/main.rs"));
+    }
+
+    #[test]
+    fn parses_files() {
+        let file = parse(
+            "root = true\n\n# comment\n[*]\nindent_style = space\n; other\n[*.md]\nTRIM_TRAILING_WHITESPACE = false\n",
+        );
+
+        assert!(file.root);
+        assert_eq!(file.sections.len(), 2);
+        assert_eq!(file.sections[0].glob, "*");
+        assert_eq!(
+            file.sections[1].pairs,
+            [("trim_trailing_whitespace".into(), "false".into())]
+        );
+    }
+
+    #[test]
+    fn nearer_files_override() {
+        let files = [
+            (
+                PathBuf::from("/repo/docs"),
+                parse("[*.md]\nindent_size = 2\ncharset = unset"),
+            ),
+            (
+                PathBuf::from("/repo"),
+                parse("root = true\n[*]\nindent_style = space\nindent_size = 4\ncharset = utf-8-bom\nend_of_line = CRLF\n[*.md]\ninsert_final_newline = false"),
+            ),
+        ];
+
+        let docs = properties(&files, Path::new("/repo/docs/readme.md"));
+        assert_eq!(docs.indent_style, Some(IndentStyle::Space));
+        assert_eq!(docs.indent_size, Some(IndentSize::Columns(2)));
+        assert_eq!(docs.tab_width(), Some(2));
+        assert_eq!(docs.soft_tab(), Some(2));
+        assert_eq!(docs.end_of_line, Some(LineEnding::CrLf));
+        assert_eq!(docs.charset, None);
+        assert_eq!(docs.insert_final_newline, Some(false));
+
+        let source = properties(&files, Path::new("/repo/src/main.rs"));
+        assert_eq!(source.charset, Some(Charset::Utf8Bom));
+        assert_eq!(source.insert_final_newline, None);
+    }
+
+    #[test]
+    fn applies_to_format() {
+        let mut properties = Properties::default();
+        properties.set("end_of_line", "crlf");
+        properties.set("charset", "utf-8-bom");
+        properties.set("insert_final_newline", "false");
+        properties.set("indent_size", "0");
+
+        let mut format = Format::default();
+        properties.apply(&mut format);
+
+        assert_eq!(format.ending, LineEnding::CrLf);
+        assert!(format.bom);
+        assert!(!format.final_newline);
+        assert_eq!(properties.indent_size, None);
+        assert_eq!(properties.soft_tab(), None);
+    }
+
+    #[test]
+    fn ignores_oversized_widths() {
+        let mut properties = Properties::default();
+        properties.set("indent_style", "space");
+        properties.set("indent_size", "99999999");
+        properties.set("tab_width", "17");
+
+        assert_eq!(properties.tab_width(), None);
+        assert_eq!(properties.soft_tab(), Some(4));
+
+        properties.set("tab_width", "16");
+        assert_eq!(properties.tab_width(), Some(16));
+    }
+
+    #[test]
+    fn indents_by_tab_width() {
+        let mut properties = Properties::default();
+        properties.set("indent_style", "space");
+        properties.set("indent_size", "tab");
+
+        assert_eq!(properties.indent_size, Some(IndentSize::Tab));
+        assert_eq!(properties.tab_width(), None);
+        assert_eq!(properties.soft_tab(), Some(4));
+
+        properties.set("tab_width", "3");
+        assert_eq!(properties.tab_width(), Some(3));
+        assert_eq!(properties.soft_tab(), Some(3));
+    }
+}
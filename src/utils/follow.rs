@@ -0,0 +1,138 @@
+use crate::{core::Res, utils::log};
+use encoding_rs::Encoding;
+use std::{
+    fs::{self, File, Metadata},
+    io::{Read, Seek, SeekFrom},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, Weak,
+    },
+    thread,
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug)]
+pub enum Event {
+    Append(String),
+    Reset(String),
+    Stopped(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Follower {
+    events: Arc<Mutex<Receiver<Event>>>,
+}
+
+impl Follower {
+    pub fn spawn(path: &Path, offset: u64, encoding: &'static Encoding) -> Self {
+        let (sender, events) = mpsc::channel();
+        let events = Arc::new(Mutex::new(events));
+        let alive = Arc::downgrade(&events);
+        let path = path.to_path_buf();
+
+        thread::spawn(move || {
+            let mut tail = Tail {
+                path,
+                offset,
+                encoding,
+                alive,
+                sender,
+            };
+
+            let reason = match panic::catch_unwind(AssertUnwindSafe(|| tail.run())) {
+                Ok(Ok(())) => return,
+                Ok(Err(error)) => format!("{error:#}"),
+                Err(_) => "the follow thread panicked".into(),
+            };
+
+            log::warning!("stopped following {}: {reason}", tail.path.display());
+            let _ = tail.sender.send(Event::Stopped(reason));
+        });
+
+        Self { events }
+    }
+
+    pub fn events(&self) -> Vec<Event> {
+        match self.events.lock() {
+            Ok(events) => events.try_iter().collect(),
+            Err(_) => vec![],
+        }
+    }
+}
+
+/// Tells a rotated file apart from the one being followed. Without inodes the creation time
+/// stands in, and a modification time that goes backwards also counts as a replacement.
+#[cfg(unix)]
+fn identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity(metadata: &Metadata) -> Option<std::time::SystemTime> {
+    metadata.created().ok()
+}
+
+struct Tail {
+    path: PathBuf,
+    offset: u64,
+    encoding: &'static Encoding,
+    alive: Weak<Mutex<Receiver<Event>>>,
+    sender: Sender<Event>,
+}
+
+impl Tail {
+    fn run(&mut self) -> Res {
+        let metadata = fs::metadata(&self.path)?;
+        let (mut id, mut modified) = (identity(&metadata), metadata.modified().ok());
+        let mut decoder = self.encoding.new_decoder_without_bom_handling();
+
+        while self.alive.strong_count() > 0 {
+            thread::sleep(POLL_INTERVAL);
+
+            let Ok(metadata) = fs::metadata(&self.path) else {
+                continue;
+            };
+
+            let reset = identity(&metadata) != id
+                || metadata.len() < self.offset
+                || metadata.modified().ok() < modified;
+            modified = metadata.modified().ok();
+            if reset {
+                log::info!("{} was truncated or replaced", self.path.display());
+                id = identity(&metadata);
+                self.offset = 0;
+                decoder = self.encoding.new_decoder_with_bom_removal();
+            } else if metadata.len() == self.offset {
+                continue;
+            }
+
+            let mut file = File::open(&self.path)?;
+            let mut bytes = vec![];
+            file.seek(SeekFrom::Start(self.offset))?;
+            file.read_to_end(&mut bytes)?;
+            self.offset += u64::try_from(bytes.len())?;
+
+            let capacity = decoder
+                .max_utf8_buffer_length(bytes.len())
+                .unwrap_or(bytes.len() * 3);
+            let mut text = String::with_capacity(capacity);
+            let _ = decoder.decode_to_string(&bytes, &mut text, false);
+
+            let event = match reset {
+                true => Event::Reset(text),
+                false => Event::Append(text),
+            };
+            if self.sender.send(event).is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
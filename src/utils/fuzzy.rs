@@ -0,0 +1,61 @@
+const MATCH_SCORE: i64 = 1;
+const CONSECUTIVE_BONUS: i64 = 4;
+const BOUNDARY_BONUS: i64 = 6;
+const GAP_PENALTY: i64 = 1;
+
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let mut query = query.chars().filter(|c| !c.is_whitespace()).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut consecutive = false;
+
+    for c in candidate.chars() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+
+        if c.to_lowercase().eq(wanted.to_lowercase()) {
+            score += MATCH_SCORE;
+            if consecutive {
+                score += CONSECUTIVE_BONUS;
+            }
+            if previous.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += BOUNDARY_BONUS;
+            }
+
+            query.next();
+            consecutive = true;
+        } else {
+            if previous.is_some() {
+                score -= GAP_PENALTY;
+            }
+            consecutive = false;
+        }
+
+        previous = Some(c);
+    }
+
+    query.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_subsequence() {
+        assert!(score("bsv", "buffer.save").is_some());
+        assert!(score("BS", "buffer.save").is_some());
+        assert!(score("sb", "buffer.save").is_none());
+        assert_eq!(score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn prefers_boundaries_and_runs() {
+        let save = score("save", "buffer.save").unwrap();
+        let scattered = score("save", "search.start-above").unwrap_or(i64::MIN);
+
+        assert!(save > scattered);
+        assert!(score("cu", "cursor.up").unwrap() > score("cu", "edit.cut").unwrap());
+    }
+}
@@ -5,6 +5,7 @@ use std::{
     cell::Cell,
     sync::mpsc::{self, Receiver},
     thread::{self, JoinHandle},
+    time::Duration,
 };
 
 pub struct InputReader(Cell<Option<JoinHandle<Res>>>, Receiver<Event>);
@@ -23,7 +24,7 @@ impl InputReader {
         )
     }
 
-    pub fn read(&self) -> Res<impl Iterator<Item = Event> + '_> {
+    pub fn read(&self, timeout: Duration) -> Res<impl Iterator<Item = Event> + '_> {
         if let Some(join_handle) = self.0.take() {
             if join_handle.is_finished() {
                 Err(join_handle
@@ -32,7 +33,8 @@ impl InputReader {
                     .expect_err("input thread only returns errors"))
             } else {
                 self.0.set(Some(join_handle));
-                Ok(self.1.try_iter())
+                let first = self.1.recv_timeout(timeout).ok();
+                Ok(first.into_iter().chain(self.1.try_iter()))
             }
         } else {
             Err(anyhow!("input thread cannot be read from after an error"))
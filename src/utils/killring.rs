@@ -0,0 +1,82 @@
+use std::collections::VecDeque;
+
+const KILL_RING_SIZE: usize = 60;
+
+#[derive(Default, Debug)]
+pub struct KillRing {
+    entries: VecDeque<String>,
+    yanked: usize,
+}
+
+impl KillRing {
+    pub fn kill(&mut self, text: &str, append: bool) {
+        match self.entries.front_mut() {
+            Some(front) if append => front.push_str(text),
+            _ => {
+                if self.entries.len() == KILL_RING_SIZE {
+                    self.entries.pop_back();
+                }
+                self.entries.push_front(text.into());
+            }
+        }
+        self.yanked = 0;
+    }
+
+    pub fn yank(&mut self) -> Option<&str> {
+        self.yanked = 0;
+        self.entries.front().map(String::as_str)
+    }
+
+    pub fn rotate(&mut self) -> Option<&str> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        self.yanked = (self.yanked + 1) % self.entries.len();
+        self.entries.get(self.yanked).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consecutive_kills_accumulate() {
+        let mut ring = KillRing::default();
+        ring.kill("one", false);
+        ring.kill("\n", true);
+        ring.kill("two", true);
+
+        assert_eq!(ring.yank(), Some("one\ntwo"));
+        assert_eq!(ring.rotate(), Some("one\ntwo"));
+    }
+
+    #[test]
+    fn rotates_through_older_kills() {
+        let mut ring = KillRing::default();
+        assert_eq!(ring.yank(), None);
+        assert_eq!(ring.rotate(), None);
+
+        for text in ["a", "b", "c"] {
+            ring.kill(text, false);
+        }
+
+        assert_eq!(ring.yank(), Some("c"));
+        assert_eq!(ring.rotate(), Some("b"));
+        assert_eq!(ring.rotate(), Some("a"));
+        assert_eq!(ring.rotate(), Some("c"));
+        assert_eq!(ring.yank(), Some("c"));
+    }
+
+    #[test]
+    fn drops_oldest_when_full() {
+        let mut ring = KillRing::default();
+        for i in 0..=KILL_RING_SIZE {
+            ring.kill(&i.to_string(), false);
+        }
+
+        let oldest = (1..KILL_RING_SIZE).fold(None, |_, _| ring.rotate().map(str::to_owned));
+        assert_eq!(oldest.as_deref(), Some("1"));
+    }
+}
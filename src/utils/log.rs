@@ -0,0 +1,181 @@
+use crate::{core::Res, utils::dirs};
+use anyhow::{anyhow, Context};
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const LOG_FILE: &str = "neonano.log";
+const KEPT_RECORDS: usize = 1_000;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+            Self::Trace => "TRACE",
+        })
+    }
+}
+
+impl FromStr for Level {
+    type Err = anyhow::Error;
+
+    fn from_str(level: &str) -> Res<Self> {
+        Ok(match level.to_ascii_lowercase().as_str() {
+            "error" => Self::Error,
+            "warn" => Self::Warn,
+            "info" => Self::Info,
+            "debug" => Self::Debug,
+            "trace" => Self::Trace,
+            _ => return Err(anyhow!("unknown log level '{level}'")),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub time: SystemTime,
+    pub level: Level,
+    pub message: String,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let since_epoch = self.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = since_epoch.as_secs() % (24 * 60 * 60);
+
+        write!(
+            f,
+            "{:02}:{:02}:{:02}.{:03} {:<5} {}",
+            secs / (60 * 60),
+            secs / 60 % 60,
+            secs % 60,
+            since_epoch.subsec_millis(),
+            self.level,
+            self.message
+        )
+    }
+}
+
+struct Logger {
+    level: Level,
+    file: Option<File>,
+    records: VecDeque<Record>,
+}
+
+static LOGGER: Mutex<Logger> = Mutex::new(Logger {
+    level: Level::Info,
+    file: None,
+    records: VecDeque::new(),
+});
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::state_dir().map(|dir| dir.join(LOG_FILE))
+}
+
+pub fn init(path: Option<&Path>, level: Level) -> Res {
+    let file = match path {
+        Some(path) => Some(
+            open(path).with_context(|| format!("failed to open log file {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let mut logger = LOGGER.lock().map_err(|_| anyhow!("logger poisoned"))?;
+    logger.level = level;
+    logger.file = file;
+
+    Ok(())
+}
+
+fn open(path: &Path) -> Res<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+pub fn log(level: Level, message: fmt::Arguments) {
+    let Ok(mut logger) = LOGGER.lock() else {
+        return;
+    };
+
+    if level > logger.level {
+        return;
+    }
+
+    let record = Record {
+        time: SystemTime::now(),
+        level,
+        message: message.to_string(),
+    };
+
+    if let Some(file) = &mut logger.file {
+        let _ = writeln!(file, "{record}");
+    }
+
+    if logger.records.len() == KEPT_RECORDS {
+        logger.records.pop_front();
+    }
+    logger.records.push_back(record);
+}
+
+pub fn records() -> Vec<Record> {
+    LOGGER
+        .lock()
+        .map(|logger| logger.records.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {
+        crate::utils::log::log(crate::utils::log::Level::Error, format_args!($($arg)*))
+    };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        crate::utils::log::log(crate::utils::log::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        crate::utils::log::log(crate::utils::log::Level::Info, format_args!($($arg)*))
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        crate::utils::log::log(crate::utils::log::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+#[allow(unused_macros)]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        crate::utils::log::log(crate::utils::log::Level::Trace, format_args!($($arg)*))
+    };
+}
+
+#[allow(unused_imports)]
+pub(crate) use {debug, error, info, trace, warning};
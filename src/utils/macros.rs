@@ -0,0 +1,231 @@
+use crate::{core::Res, keymap, message::KeyCombo, utils::dirs, vi::MAX_COUNT};
+use anyhow::{anyhow, bail, Context};
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::PathBuf};
+
+#[derive(Clone, Debug)]
+struct Recording {
+    name: String,
+    keys: Vec<KeyCombo>,
+    checkpoint: usize,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct Macros {
+    registers: BTreeMap<String, Vec<KeyCombo>>,
+    recording: Option<Recording>,
+    last: Option<String>,
+}
+
+fn path() -> Option<PathBuf> {
+    dirs::state_dir().map(|dir| dir.join("macros.toml"))
+}
+
+pub fn parse_name(text: &str) -> Res<&str> {
+    let name = text.trim();
+
+    if name.is_empty() {
+        bail!("macro name cannot be empty");
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) || name.contains(char::is_whitespace) {
+        bail!("invalid macro name '{name}'");
+    }
+
+    Ok(name)
+}
+
+impl Macros {
+    pub fn recording(&self) -> Option<&str> {
+        self.recording
+            .as_ref()
+            .map(|recording| recording.name.as_str())
+    }
+
+    pub fn last(&self) -> Option<&str> {
+        self.last.as_deref()
+    }
+
+    pub fn start(&mut self, name: &str) {
+        self.recording = Some(Recording {
+            name: name.into(),
+            keys: vec![],
+            checkpoint: 0,
+        });
+    }
+
+    pub fn record(&mut self, combo: KeyCombo, idle: bool) {
+        if let Some(recording) = &mut self.recording {
+            if idle {
+                recording.checkpoint = recording.keys.len();
+            }
+            recording.keys.push(combo);
+        }
+    }
+
+    pub fn stop(&mut self) -> Option<(String, usize)> {
+        let Recording {
+            name,
+            mut keys,
+            checkpoint,
+        } = self.recording.take()?;
+        keys.truncate(checkpoint);
+
+        let len = keys.len();
+        self.registers.insert(name.clone(), keys);
+        self.last = Some(name.clone());
+
+        Some((name, len))
+    }
+
+    pub fn invoke(&mut self, text: &str) -> Res<(usize, Vec<KeyCombo>)> {
+        let text = text.trim();
+        let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let count = match digits {
+            0 => 1,
+            _ => text[..digits].parse().unwrap_or(MAX_COUNT).min(MAX_COUNT),
+        };
+
+        let name = match text[digits..].trim() {
+            "" => self.last.clone().context("no macro has been recorded")?,
+            name => parse_name(name)?.to_owned(),
+        };
+        let keys = self
+            .registers
+            .get(&name)
+            .cloned()
+            .with_context(|| format!("no macro named '{name}'"))?;
+        self.last = Some(name);
+
+        Ok((count, keys))
+    }
+
+    fn to_toml(&self) -> Res<String> {
+        let registers: BTreeMap<_, _> = self
+            .registers
+            .iter()
+            .filter(|(_, keys)| !keys.is_empty())
+            .map(|(name, keys)| (name, keymap::display_sequence(keys)))
+            .collect();
+
+        Ok(toml::to_string(&registers)?)
+    }
+
+    fn from_toml(text: &str) -> Res<Self> {
+        let registers: BTreeMap<String, String> =
+            toml::from_str(text).map_err(|error| anyhow!("{}", error.message().trim_end()))?;
+        let registers = registers
+            .into_iter()
+            .map(|(name, keys)| {
+                let keys = keymap::parse_sequence(&keys)
+                    .with_context(|| format!("invalid macro '{name}'"))?;
+                Ok((name, keys))
+            })
+            .collect::<Res<_>>()?;
+
+        Ok(Self {
+            registers,
+            ..Self::default()
+        })
+    }
+
+    pub fn load() -> Res<Self> {
+        let Some(path) = path() else {
+            return Ok(Self::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::from_toml(&text)
+                .map_err(|error| anyhow!("invalid macros {}: {error:#}", path.display())),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => {
+                Err(error).with_context(|| format!("failed to read macros {}", path.display()))
+            }
+        }
+    }
+
+    pub fn save(&self) -> Res {
+        let path = path().context("no state directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&path, self.to_toml()?)
+            .with_context(|| format!("failed to write macros {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(text: &str) -> Vec<KeyCombo> {
+        keymap::parse_sequence(text).unwrap()
+    }
+
+    fn record(macros: &mut Macros, name: &str, text: &str) {
+        macros.start(name);
+        for combo in keys(text) {
+            macros.record(combo, true);
+        }
+    }
+
+    #[test]
+    fn stop_drops_the_stopping_keys() {
+        let mut macros = Macros::default();
+        macros.start("a");
+        for (combo, idle) in keys("x y ctrl+k s")
+            .into_iter()
+            .zip([true, true, true, false])
+        {
+            macros.record(combo, idle);
+        }
+
+        assert_eq!(macros.recording(), Some("a"));
+        assert_eq!(macros.stop(), Some(("a".into(), 2)));
+        assert_eq!(macros.recording(), None);
+        assert_eq!(macros.invoke("a").unwrap(), (1, keys("x y")));
+    }
+
+    #[test]
+    fn invocations() {
+        let mut macros = Macros::default();
+        assert!(macros.invoke("").is_err());
+
+        record(&mut macros, "a", "x f5");
+        macros.stop();
+        record(&mut macros, "word", "y f5");
+        macros.stop();
+
+        assert_eq!(macros.invoke("3a").unwrap(), (3, keys("x")));
+        assert_eq!(macros.invoke("").unwrap(), (1, keys("x")));
+        assert_eq!(macros.invoke("12 word").unwrap(), (12, keys("y")));
+        assert_eq!(macros.last(), Some("word"));
+        assert_eq!(
+            macros.invoke("99999999999999999999999a").unwrap(),
+            (MAX_COUNT, keys("x"))
+        );
+        assert!(macros.invoke("b").is_err());
+        assert!(macros.invoke("2 a b").is_err());
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(parse_name(" q ").unwrap(), "q");
+        assert!(parse_name("").is_err());
+        assert!(parse_name("1a").is_err());
+        assert!(parse_name("a b").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let mut macros = Macros::default();
+        record(&mut macros, "a", "ctrl+x shift+A space + enter f5");
+        macros.stop();
+
+        let mut loaded = Macros::from_toml(&macros.to_toml().unwrap()).unwrap();
+        assert_eq!(
+            loaded.invoke("a").unwrap(),
+            (1, keys("ctrl+x shift+A space + enter"))
+        );
+        assert!(Macros::from_toml("a = \"ctrl+\"").is_err());
+    }
+}
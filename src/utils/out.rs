@@ -1,12 +1,54 @@
-use crate::core::Res;
+use crate::{core::Res, utils::shared};
+use anyhow::Context;
 use crossterm::{
     cursor::{MoveDown, MoveLeft, MoveTo, MoveToColumn, RestorePosition, SavePosition},
     queue,
-    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    style::{Print, ResetColor, SetBackgroundColor, SetForegroundColor},
 };
-use std::iter;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, IsTerminal, StdoutLock, Write},
+    iter,
+};
+
+pub type Out = BufWriter<Terminal>;
+
+#[derive(Debug)]
+pub enum Terminal {
+    Stdout(StdoutLock<'static>),
+    Tty(File),
+}
+
+impl Terminal {
+    pub fn open() -> Res<Self> {
+        if io::stdout().is_terminal() {
+            Ok(Self::Stdout(io::stdout().lock()))
+        } else {
+            Ok(Self::Tty(
+                OpenOptions::new()
+                    .write(true)
+                    .open("/dev/tty")
+                    .context("stdout is not a terminal and /dev/tty is unavailable")?,
+            ))
+        }
+    }
+}
+
+impl Write for Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::Tty(tty) => tty.write(buf),
+        }
+    }
 
-pub type Out = std::io::StdoutLock<'static>;
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::Tty(tty) => tty.flush(),
+        }
+    }
+}
 
 #[derive(Copy, Clone, Default, Debug)]
 pub struct Bounds {
@@ -98,10 +140,11 @@ pub fn with_highlighted<'out, F>(out: &'out mut Out, f: F) -> Res<&'out mut Out>
 where
     F: FnOnce(&'out mut Out) -> Res<&'out mut Out>,
 {
+    let colors = shared::get(|shared| shared.config.colors.clone());
     queue!(
         out,
-        SetBackgroundColor(Color::White),
-        SetForegroundColor(Color::Black),
+        SetBackgroundColor(colors.highlight),
+        SetForegroundColor(colors.highlight_text),
     )?;
     let out = f(out)?;
     queue!(out, ResetColor)?;
@@ -1,40 +1,22 @@
+use crate::{config::Config, keymap::Keymap, utils::killring::KillRing, vi::Register};
 use std::cell::RefCell;
 
 #[derive(Default, Debug)]
 pub struct Shared {
-    _debug: String,
+    pub config: Config,
+    pub keymap: Keymap,
+    pub register: Register,
+    pub kill_ring: KillRing,
 }
 
 thread_local! {
     static SHARED: RefCell<Shared> = Default::default();
 }
 
-pub fn _get<Ret>(f: impl FnOnce(&Shared) -> Ret) -> Ret {
+pub fn get<Ret>(f: impl FnOnce(&Shared) -> Ret) -> Ret {
     SHARED.with_borrow(|shared| f(shared))
 }
 
-pub fn _set<Ret>(f: impl FnOnce(&mut Shared) -> Ret) -> Ret {
+pub fn set<Ret>(f: impl FnOnce(&mut Shared) -> Ret) -> Ret {
     SHARED.with_borrow_mut(|shared| f(shared))
 }
-
-#[allow(unused_macros)]
-macro_rules! debug {
-    () => {
-        crate::utils::shared::set(|shared| {
-            use std::fmt::Write;
-            write!(&mut shared.debug, "line: {}", line!())?;
-            crate::core::Res::Ok(())
-        })??
-    };
-
-    ($($arg:tt)*) => {
-        crate::utils::shared::set(|shared| {
-            use std::fmt::Write;
-            write!(&mut shared.debug, "line: {} msg: ", line!())?;
-            shared.debug.write_fmt(format_args!($($arg)*))?;
-            crate::core::Res::Ok(())
-        })??
-    }
-}
-#[allow(unused_imports)]
-pub(crate) use debug;
@@ -0,0 +1,275 @@
+use crate::{core::Res, utils::dirs};
+use anyhow::{anyhow, bail, Context};
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{self, Path, PathBuf},
+    process,
+};
+
+const HEADER: &str = "neonano swap\n";
+
+/// The process that wrote a swap file, recorded on the line after the header so that a second
+/// instance can tell a swap file that is still in use from one left behind by a crash.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Owner {
+    pub pid: u32,
+    pub host: String,
+}
+
+impl Owner {
+    fn current() -> Self {
+        Self {
+            pid: process::id(),
+            host: hostname(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let (pid, host) = line.split_once(' ')?;
+
+        Some(Self {
+            pid: pid.parse().ok()?,
+            host: host.into(),
+        })
+    }
+
+    /// Processes on other hosts cannot be checked, so their swap files count as abandoned.
+    pub fn is_alive(&self) -> bool {
+        self.host == hostname() && is_running(self.pid)
+    }
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "process {} on {}", self.pid, self.host)
+    }
+}
+
+#[derive(Debug)]
+pub struct Swap {
+    pub owner: Owner,
+    pub text: String,
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut name = [0u8; 256];
+    // SAFETY: the buffer is valid for writes of its whole length.
+    let result = unsafe { libc::gethostname(name.as_mut_ptr().cast(), name.len()) };
+    let len = name
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(name.len());
+
+    match result {
+        0 => String::from_utf8_lossy(&name[..len]).into(),
+        _ => "localhost".into(),
+    }
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "localhost".into())
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks whether the process exists and may be signalled.
+    let result = unsafe { libc::kill(pid, 0) };
+
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_running(pid: u32) -> bool {
+    pid == process::id()
+}
+
+fn beside(file: &Path) -> Option<PathBuf> {
+    let name = file.file_name()?.to_string_lossy();
+
+    Some(file.with_file_name(format!(".{name}.neonano.swp")))
+}
+
+fn in_state_dir(file: &Path) -> Option<PathBuf> {
+    let absolute = path::absolute(file).ok()?;
+    let name = absolute.to_string_lossy().replace(['/', '\\'], "%");
+
+    Some(dirs::state_dir()?.join("swap").join(format!("{name}.swp")))
+}
+
+fn is_ours(swap: &Path) -> bool {
+    let mut header = [0; HEADER.len()];
+
+    File::open(swap)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| header == HEADER.as_bytes())
+}
+
+fn owner(swap: &Path) -> Option<Owner> {
+    let mut lines = BufReader::new(File::open(swap).ok()?).lines();
+    lines.next()?.ok()?;
+
+    Owner::parse(&lines.next()?.ok()?)
+}
+
+pub fn find(file: &Path) -> Option<PathBuf> {
+    [beside(file), in_state_dir(file)]
+        .into_iter()
+        .flatten()
+        .find(|swap| swap.is_file() && is_ours(swap))
+}
+
+pub fn read(swap: &Path) -> Res<Swap> {
+    let contents = fs::read_to_string(swap)?;
+    let not_ours = || anyhow!("{} is not a neonano swap file", swap.display());
+
+    let (owner, text) = contents
+        .strip_prefix(HEADER)
+        .and_then(|rest| rest.split_once('\n'))
+        .ok_or_else(not_ours)?;
+    let owner = Owner::parse(owner).ok_or_else(not_ours)?;
+
+    Ok(Swap {
+        owner,
+        text: text.into(),
+    })
+}
+
+fn create(path: &Path, file: &Path) -> Res<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        // Never make the swap file more readable than the file it journals.
+        let mode = fs::metadata(file).map_or(0o600, |metadata| metadata.permissions().mode());
+        options.mode(mode & 0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = file;
+
+    Ok(options.open(path)?)
+}
+
+pub fn write(file: &Path, text: impl Fn(&mut dyn Write) -> Res) -> Res<PathBuf> {
+    let current = Owner::current();
+    let mut last_error = None;
+
+    for swap in [beside(file), in_state_dir(file)].into_iter().flatten() {
+        let written = (|| -> Res {
+            if let Some(parent) = swap.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if swap.exists() {
+                if !is_ours(&swap) {
+                    bail!("{} is not a neonano swap file", swap.display());
+                }
+                if let Some(owner) = owner(&swap).filter(|owner| *owner != current) {
+                    if owner.is_alive() {
+                        bail!("{} is in use by {owner}", swap.display());
+                    }
+                }
+            }
+
+            // Write a temporary file and rename it over the swap file, so that a crash mid-write
+            // never leaves a torn swap file behind.
+            let name = swap.file_name().context("swap file has a name")?;
+            let temp =
+                swap.with_file_name(format!("{}.{}.tmp", name.to_string_lossy(), current.pid));
+            let result = (|| -> Res {
+                let mut writer = BufWriter::new(create(&temp, file)?);
+                writeln!(writer, "{HEADER}{} {}", current.pid, current.host)?;
+                text(&mut writer)?;
+                writer
+                    .into_inner()
+                    .map_err(|error| error.into_error())?
+                    .sync_all()?;
+
+                Ok(fs::rename(&temp, &swap)?)
+            })();
+            if result.is_err() {
+                let _ = fs::remove_file(&temp);
+            }
+
+            result
+        })();
+
+        match written {
+            Ok(()) => return Ok(swap),
+            Err(error) => last_error = Some(error),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow!("no location for swap file")))
+}
+
+pub fn remove(swap: &Path) -> Res {
+    match fs::remove_file(swap) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn records_the_owner() {
+        let file = env::temp_dir().join(format!("neonano-swap-{}.txt", process::id()));
+        fs::write(&file, "text").unwrap();
+
+        let swap = write(&file, |writer| Ok(writer.write_all(b"edited\n")?)).unwrap();
+        assert_eq!(swap, beside(&file).unwrap());
+        assert_eq!(find(&file), Some(swap.clone()));
+
+        let Swap { owner, text } = read(&swap).unwrap();
+        assert_eq!(owner, Owner::current());
+        assert!(owner.is_alive());
+        assert_eq!(text, "edited\n");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&swap).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        remove(&swap).unwrap();
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn leaves_live_swap_files_alone() {
+        let file = env::temp_dir().join(format!("neonano-live-{}.txt", process::id()));
+        let swap = beside(&file).unwrap();
+        let mut other = process::Command::new("sleep").arg("10").spawn().unwrap();
+
+        let live = format!("{HEADER}{} {}\nother\n", other.id(), hostname());
+        let dead = format!("{HEADER}{} elsewhere\nother\n", other.id());
+        for (contents, replaced) in [(live, false), (dead, true)] {
+            fs::write(&swap, contents).unwrap();
+            let _ = write(&file, |writer| Ok(writer.write_all(b"mine\n")?));
+
+            assert_eq!(read(&swap).unwrap().text == "mine\n", replaced);
+        }
+
+        other.kill().unwrap();
+        other.wait().unwrap();
+        remove(&swap).unwrap();
+        if let Some(state) = in_state_dir(&file) {
+            remove(&state).unwrap();
+        }
+    }
+}
@@ -0,0 +1,66 @@
+pub fn slots(template: &str) -> [&str; 3] {
+    let mut slots = template.splitn(3, '|').map(str::trim);
+
+    [(); 3].map(|()| slots.next().unwrap_or_default())
+}
+
+pub fn render<'value>(template: &str, lookup: impl Fn(&str) -> Option<&'value str>) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        if let Some(escaped) = rest.strip_prefix("{{").or(rest.strip_prefix("}}")) {
+            rendered.push_str(&rest[..1]);
+            rest = escaped;
+        } else if let Some((name, after)) =
+            rest[1..].split_once('}').filter(|_| rest.starts_with('{'))
+        {
+            rendered.push_str(lookup(name.trim()).unwrap_or_default());
+            rest = after;
+        } else {
+            rendered.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    rendered.push_str(rest);
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<&'static str> {
+        match name {
+            "path" => Some("src/main.rs"),
+            "dirty" => Some(" [+]"),
+            "line" => Some("12"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn splits_slots() {
+        assert_eq!(slots("{path} | {line} | x"), ["{path}", "{line}", "x"]);
+        assert_eq!(slots("{path}"), ["{path}", "", ""]);
+        assert_eq!(slots("a | b | c | d"), ["a", "b", "c | d"]);
+    }
+
+    #[test]
+    fn substitutes_fields() {
+        assert_eq!(render("{path}{dirty}", lookup), "src/main.rs [+]");
+        assert_eq!(render("line { line }", lookup), "line 12");
+        assert_eq!(render("{missing}!", lookup), "!");
+    }
+
+    #[test]
+    fn escapes_and_stray_braces() {
+        assert_eq!(render("{{path}}", lookup), "{path}");
+        assert_eq!(render("{path", lookup), "{path");
+        assert_eq!(render("a } b", lookup), "a } b");
+    }
+}
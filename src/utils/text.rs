@@ -0,0 +1,350 @@
+use crate::core::Res;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::{
+    fmt,
+    io::{self, ErrorKind, Write},
+};
+
+const UTF_16_SAMPLE: usize = 4096;
+const BINARY_SAMPLE: usize = 8192;
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+            Self::Cr => "\r",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Lf => Self::CrLf,
+            Self::CrLf => Self::Cr,
+            Self::Cr => Self::Lf,
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+            Self::Cr => "CR",
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Format {
+    pub encoding: &'static Encoding,
+    pub ending: LineEnding,
+    pub bom: bool,
+    pub final_newline: bool,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            ending: LineEnding::Lf,
+            bom: false,
+            final_newline: true,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.encoding.name(), self.ending)?;
+        if self.bom {
+            write!(f, " BOM")?;
+        }
+        if !self.final_newline {
+            write!(f, " noeol")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_data(message: String) -> anyhow::Error {
+    io::Error::new(ErrorKind::InvalidData, message).into()
+}
+
+fn bom(encoding: &'static Encoding) -> &'static [u8] {
+    if encoding == UTF_8 {
+        b"\xEF\xBB\xBF"
+    } else if encoding == UTF_16LE {
+        b"\xFF\xFE"
+    } else if encoding == UTF_16BE {
+        b"\xFE\xFF"
+    } else {
+        b""
+    }
+}
+
+fn sniff_utf_16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(UTF_16_SAMPLE) & !1];
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+
+    let (even, odd) = sample.chunks_exact(2).fold((0, 0), |(even, odd), pair| {
+        (
+            even + usize::from(pair[0] == 0),
+            odd + usize::from(pair[1] == 0),
+        )
+    });
+
+    if odd * 10 >= pairs * 4 && even * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even * 10 >= pairs * 4 && odd * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+pub fn detect(bytes: &[u8]) -> (&'static Encoding, bool) {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        (encoding, true)
+    } else if let Some(encoding) = sniff_utf_16(bytes) {
+        (encoding, false)
+    } else if std::str::from_utf8(bytes).is_ok() {
+        (UTF_8, false)
+    } else {
+        let mut detector = EncodingDetector::new();
+        detector.feed(bytes, true);
+
+        (detector.guess(None, true), false)
+    }
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    Encoding::for_bom(bytes).is_none()
+        && sniff_utf_16(bytes).is_none()
+        && bytes[..bytes.len().min(BINARY_SAMPLE)].contains(&0)
+}
+
+pub fn decode(bytes: &[u8]) -> Res<(Vec<String>, Format)> {
+    let (encoding, bom) = detect(bytes);
+
+    decode_as(bytes, encoding, bom)
+}
+
+pub fn decode_as(
+    bytes: &[u8],
+    encoding: &'static Encoding,
+    has_bom: bool,
+) -> Res<(Vec<String>, Format)> {
+    let bytes = match has_bom {
+        true => bytes.strip_prefix(bom(encoding)).unwrap_or(bytes),
+        false => bytes,
+    };
+    let text = encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .ok_or_else(|| invalid_data(format!("file cannot be decoded as {}", encoding.name())))?;
+
+    let (lines, format) = split(&text, has_bom);
+
+    Ok((lines, Format { encoding, ..format }))
+}
+
+fn split(text: &str, bom: bool) -> (Vec<String>, Format) {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    let ending = match (lf, crlf) {
+        (0, 0) if text.contains('\r') => LineEnding::Cr,
+        (0, 1..) => LineEnding::CrLf,
+        _ => LineEnding::Lf,
+    };
+
+    // Only the detected terminator ends a line. Stray carriage returns in mixed files stay part
+    // of the line, so saving an unedited buffer writes back the bytes it was read from.
+    let mut lines: Vec<String> = text.split(ending.as_str()).map(Into::into).collect();
+    let final_newline = !text.is_empty() && lines.last().is_some_and(String::is_empty);
+    if final_newline || text.is_empty() {
+        lines.pop();
+    }
+
+    (
+        lines,
+        Format {
+            encoding: UTF_8,
+            ending,
+            bom,
+            final_newline,
+        },
+    )
+}
+
+pub fn join<'line>(lines: impl Iterator<Item = &'line str>, format: Format) -> String {
+    let mut text = String::new();
+    let mut lines = lines.peekable();
+    while let Some(line) = lines.next() {
+        text.push_str(line);
+        if lines.peek().is_some() || format.final_newline {
+            text.push_str(format.ending.as_str());
+        }
+    }
+
+    text
+}
+
+pub fn encode<'line>(
+    writer: &mut impl Write,
+    lines: impl Iterator<Item = &'line str>,
+    format: Format,
+) -> Res {
+    if format.bom {
+        writer.write_all(bom(format.encoding))?;
+    }
+
+    let text = join(lines, format);
+    let encoding = format.encoding;
+    if encoding == UTF_8 {
+        writer.write_all(text.as_bytes())?;
+    } else if encoding == UTF_16LE || encoding == UTF_16BE {
+        for unit in text.encode_utf16() {
+            writer.write_all(&if encoding == UTF_16LE {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            })?;
+        }
+    } else {
+        let (bytes, _, unmappable) = encoding.encode(&text);
+        if unmappable {
+            return Err(invalid_data(format!(
+                "buffer contains characters that cannot be encoded as {}",
+                encoding.name()
+            )));
+        }
+        writer.write_all(&bytes)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(bytes: &[u8]) -> Vec<u8> {
+        let (lines, format) = decode(bytes).unwrap();
+        let mut out = vec![];
+        encode(&mut out, lines.iter().map(String::as_str), format).unwrap();
+        out
+    }
+
+    #[test]
+    fn detects_endings() {
+        assert_eq!(decode(b"a\nb\n").unwrap().1.ending, LineEnding::Lf);
+        assert_eq!(decode(b"a\r\nb\r\n").unwrap().1.ending, LineEnding::CrLf);
+        assert_eq!(decode(b"a\rb\r").unwrap().1.ending, LineEnding::Cr);
+        assert_eq!(decode(b"a\r\nb\nc\r\n").unwrap().1.ending, LineEnding::Lf);
+        assert_eq!(decode(b"a\rb\n").unwrap().1.ending, LineEnding::Lf);
+    }
+
+    #[test]
+    fn splits_lines() {
+        let (lines, format) = decode(b"a\r\nb\rc\r\nd").unwrap();
+        assert_eq!(lines, ["a", "b\rc", "d"]);
+        assert!(!format.final_newline);
+
+        let (lines, _) = decode(b"a\r\nb\rc\nd").unwrap();
+        assert_eq!(lines, ["a\r", "b\rc", "d"]);
+    }
+
+    #[test]
+    fn bom_and_final_newline() {
+        let (lines, format) = decode(b"\xEF\xBB\xBFa\n").unwrap();
+
+        assert_eq!(lines, ["a"]);
+        assert!(format.bom);
+        assert!(format.final_newline);
+    }
+
+    #[test]
+    fn roundtrips() {
+        for bytes in [
+            &b"a\nb\n"[..],
+            b"a\r\nb",
+            b"\xEF\xBB\xBFx\ry\r",
+            b"\xFE\xFF\0a\0\r\0\n",
+            b"",
+            b"\n",
+            b"only",
+            b"a\rb\n",
+            b"a\r\nb\nc\r\n",
+            b"\r\r\n",
+        ] {
+            assert_eq!(roundtrip(bytes), bytes);
+        }
+    }
+
+    #[test]
+    fn detects_utf_16() {
+        let (lines, format) = decode(b"\xFF\xFEa\0\n\0").unwrap();
+        assert_eq!(lines, ["a"]);
+        assert_eq!(format.encoding, UTF_16LE);
+        assert!(format.bom);
+
+        let (lines, format) = decode(b"\0h\0i\0\n").unwrap();
+        assert_eq!(lines, ["hi"]);
+        assert_eq!(format.encoding, UTF_16BE);
+        assert!(!format.bom);
+    }
+
+    #[test]
+    fn detects_legacy() {
+        let (lines, format) = decode(b"caf\xE9 cr\xE8me br\xFBl\xE9e\n").unwrap();
+
+        assert_eq!(lines, ["café crème brûlée"]);
+        assert_eq!(format.encoding, encoding_rs::WINDOWS_1252);
+        assert_eq!(
+            roundtrip(b"caf\xE9 cr\xE8me br\xFBl\xE9e\n"),
+            b"caf\xE9 cr\xE8me br\xFBl\xE9e\n"
+        );
+    }
+
+    #[test]
+    fn detects_binary() {
+        assert!(is_binary(b"\x7FELF\x02\x01\x01\0\0\0\0\0\0\0\0\0\x03\0>\0"));
+        assert!(!is_binary(b"plain text\n"));
+        assert!(!is_binary(b"\xFF\xFEa\0b\0"));
+        assert!(!is_binary(b"\0h\0i\0\n"));
+    }
+
+    #[test]
+    fn rejects_undecodable() {
+        let error = decode_as(b"\xFF\xFE\xFD", UTF_8, false).unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(ErrorKind::InvalidData)
+        );
+    }
+
+    #[test]
+    fn rejects_unencodable() {
+        let format = Format {
+            encoding: encoding_rs::WINDOWS_1252,
+            ..Format::default()
+        };
+
+        assert!(encode(&mut vec![], ["日本"].into_iter(), format).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use std::fmt;
+
+pub const MAX_COUNT: usize = 999_999;
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum Mode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Normal => "NORMAL",
+            Self::Insert => "INSERT",
+            Self::Visual => "VISUAL",
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordStart,
+    WordEnd,
+    WordBack,
+    LineStart,
+    LineEnd,
+    Top,
+    Bottom,
+    WholeLine,
+    Selection,
+}
+
+impl Motion {
+    pub fn is_linewise(self) -> bool {
+        matches!(
+            self,
+            Self::Up | Self::Down | Self::Top | Self::Bottom | Self::WholeLine
+        )
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Insert {
+    Before,
+    After,
+    LineStart,
+    LineEnd,
+    Above,
+    Below,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Action {
+    Move {
+        count: Option<usize>,
+        motion: Motion,
+    },
+    Operate {
+        count: Option<usize>,
+        operator: Operator,
+        motion: Motion,
+    },
+    Insert(Insert),
+    Visual,
+    Paste {
+        count: Option<usize>,
+        after: bool,
+    },
+    Repeat {
+        count: Option<usize>,
+    },
+}
+
+impl Action {
+    pub fn is_change(self) -> bool {
+        match self {
+            Self::Operate { operator, .. } => operator != Operator::Yank,
+            Self::Insert(_) | Self::Paste { .. } => true,
+            Self::Move { .. } | Self::Visual | Self::Repeat { .. } => false,
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct Register {
+    pub text: String,
+    pub linewise: bool,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct Parser {
+    count: Option<usize>,
+    operator: Option<(Option<usize>, Operator)>,
+    prefix: bool,
+    keys: String,
+}
+
+fn multiply(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (None, None) => None,
+        _ => Some(a.unwrap_or(1).saturating_mul(b.unwrap_or(1)).min(MAX_COUNT)),
+    }
+}
+
+fn motion(c: char) -> Option<Motion> {
+    Some(match c {
+        'h' => Motion::Left,
+        'l' | ' ' => Motion::Right,
+        'k' => Motion::Up,
+        'j' => Motion::Down,
+        'w' => Motion::WordStart,
+        'e' => Motion::WordEnd,
+        'b' => Motion::WordBack,
+        '0' => Motion::LineStart,
+        '$' => Motion::LineEnd,
+        'G' => Motion::Bottom,
+        _ => return None,
+    })
+}
+
+impl Parser {
+    pub fn pending(&self) -> &str {
+        &self.keys
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn feed(&mut self, mode: Mode, c: char) -> Option<Action> {
+        self.keys.push(c);
+
+        match self.step(mode, c) {
+            Some(action) => {
+                self.reset();
+                action
+            }
+            None => None,
+        }
+    }
+
+    fn moved(&mut self, motion: Motion) -> Action {
+        match self.operator.take() {
+            Some((count, operator)) => Action::Operate {
+                count: multiply(count, self.count.take()),
+                operator,
+                motion,
+            },
+            None => Action::Move {
+                count: self.count.take(),
+                motion,
+            },
+        }
+    }
+
+    fn step(&mut self, mode: Mode, c: char) -> Option<Option<Action>> {
+        if self.prefix {
+            self.prefix = false;
+            return Some((c == 'g').then(|| self.moved(Motion::Top)));
+        }
+
+        if let Some(digit) = c
+            .to_digit(10)
+            .filter(|&digit| digit > 0 || self.count.is_some())
+        {
+            let count = self.count.unwrap_or_default().saturating_mul(10);
+            self.count = Some(count.saturating_add(digit as usize).min(MAX_COUNT));
+            return None;
+        }
+        if c == 'g' {
+            self.prefix = true;
+            return None;
+        }
+        if let Some(motion) = motion(c) {
+            return Some(Some(self.moved(motion)));
+        }
+
+        let operator = match c {
+            'd' => Some(Operator::Delete),
+            'c' => Some(Operator::Change),
+            'y' => Some(Operator::Yank),
+            _ => None,
+        };
+        let count = self.count.take();
+
+        Some(Some(match (mode, self.operator, operator) {
+            (Mode::Visual, _, Some(operator)) => Action::Operate {
+                count: None,
+                operator,
+                motion: Motion::Selection,
+            },
+            (Mode::Visual, _, None) => match c {
+                'x' => Action::Operate {
+                    count: None,
+                    operator: Operator::Delete,
+                    motion: Motion::Selection,
+                },
+                's' => Action::Operate {
+                    count: None,
+                    operator: Operator::Change,
+                    motion: Motion::Selection,
+                },
+                'v' => Action::Visual,
+                _ => return Some(None),
+            },
+            (_, Some((pending, pending_operator)), Some(operator))
+                if pending_operator == operator =>
+            {
+                Action::Operate {
+                    count: multiply(pending, count),
+                    operator,
+                    motion: Motion::WholeLine,
+                }
+            }
+            (_, Some(_), _) => return Some(None),
+            (_, None, Some(operator)) => {
+                self.operator = Some((count, operator));
+                return None;
+            }
+            (_, None, None) => {
+                let operate = |operator, motion| Action::Operate {
+                    count,
+                    operator,
+                    motion,
+                };
+
+                match c {
+                    'i' => Action::Insert(Insert::Before),
+                    'a' => Action::Insert(Insert::After),
+                    'I' => Action::Insert(Insert::LineStart),
+                    'A' => Action::Insert(Insert::LineEnd),
+                    'O' => Action::Insert(Insert::Above),
+                    'o' => Action::Insert(Insert::Below),
+                    'v' => Action::Visual,
+                    'x' => operate(Operator::Delete, Motion::Right),
+                    'X' => operate(Operator::Delete, Motion::Left),
+                    'D' => operate(Operator::Delete, Motion::LineEnd),
+                    'C' => operate(Operator::Change, Motion::LineEnd),
+                    's' => operate(Operator::Change, Motion::Right),
+                    'S' => operate(Operator::Change, Motion::WholeLine),
+                    'Y' => operate(Operator::Yank, Motion::WholeLine),
+                    'p' => Action::Paste { count, after: true },
+                    'P' => Action::Paste {
+                        count,
+                        after: false,
+                    },
+                    '.' => Action::Repeat { count },
+                    _ => return Some(None),
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(mode: Mode, keys: &str) -> Vec<Action> {
+        let mut parser = Parser::default();
+
+        keys.chars().filter_map(|c| parser.feed(mode, c)).collect()
+    }
+
+    #[test]
+    fn motions_with_counts() {
+        assert_eq!(
+            parse(Mode::Normal, "3w"),
+            [Action::Move {
+                count: Some(3),
+                motion: Motion::WordStart
+            }]
+        );
+        assert_eq!(
+            parse(Mode::Normal, "0"),
+            [Action::Move {
+                count: None,
+                motion: Motion::LineStart
+            }]
+        );
+        assert_eq!(
+            parse(Mode::Normal, "10G"),
+            [Action::Move {
+                count: Some(10),
+                motion: Motion::Bottom
+            }]
+        );
+        assert_eq!(
+            parse(Mode::Normal, "gg"),
+            [Action::Move {
+                count: None,
+                motion: Motion::Top
+            }]
+        );
+        assert_eq!(
+            parse(Mode::Normal, "99999999999999999999j"),
+            [Action::Move {
+                count: Some(MAX_COUNT),
+                motion: Motion::Down
+            }]
+        );
+        assert_eq!(
+            parse(Mode::Normal, "9999d9999w"),
+            [Action::Operate {
+                count: Some(MAX_COUNT),
+                operator: Operator::Delete,
+                motion: Motion::WordStart
+            }]
+        );
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(
+            parse(Mode::Normal, "2d3w"),
+            [Action::Operate {
+                count: Some(6),
+                operator: Operator::Delete,
+                motion: Motion::WordStart
+            }]
+        );
+        assert_eq!(
+            parse(Mode::Normal, "yy"),
+            [Action::Operate {
+                count: None,
+                operator: Operator::Yank,
+                motion: Motion::WholeLine
+            }]
+        );
+        assert_eq!(
+            parse(Mode::Normal, "cgg"),
+            [Action::Operate {
+                count: None,
+                operator: Operator::Change,
+                motion: Motion::Top
+            }]
+        );
+        assert_eq!(
+            parse(Mode::Normal, "d$"),
+            [Action::Operate {
+                count: None,
+                operator: Operator::Delete,
+                motion: Motion::LineEnd
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_sequences_reset() {
+        let mut parser = Parser::default();
+
+        assert_eq!(parser.feed(Mode::Normal, 'd'), None);
+        assert_eq!(parser.pending(), "d");
+        assert_eq!(parser.feed(Mode::Normal, 'y'), None);
+        assert_eq!(parser.pending(), "");
+        assert_eq!(
+            parser.feed(Mode::Normal, 'x'),
+            Some(Action::Operate {
+                count: None,
+                operator: Operator::Delete,
+                motion: Motion::Right
+            })
+        );
+    }
+
+    #[test]
+    fn visual_operators() {
+        assert_eq!(
+            parse(Mode::Visual, "wd"),
+            [
+                Action::Move {
+                    count: None,
+                    motion: Motion::WordStart
+                },
+                Action::Operate {
+                    count: None,
+                    operator: Operator::Delete,
+                    motion: Motion::Selection
+                }
+            ]
+        );
+        assert_eq!(parse(Mode::Visual, "i"), []);
+    }
+
+    #[test]
+    fn changes() {
+        assert!(parse(Mode::Normal, "dw")[0].is_change());
+        assert!(parse(Mode::Normal, "o")[0].is_change());
+        assert!(!parse(Mode::Normal, "yw")[0].is_change());
+        assert!(!parse(Mode::Normal, "3.")[0].is_change());
+    }
+}